@@ -0,0 +1,19 @@
+use arweave_dumper::avro;
+use criterion::{criterion_group, criterion_main, Criterion};
+
+fn bench_tag_decoders(c: &mut Criterion) {
+    let hex_str = include_str!("../res/first_item_tags.hex");
+    let data = hex::decode(hex_str).expect("should parse");
+
+    let mut group = c.benchmark_group("parse_tag_list");
+    group.bench_function("generic", |b| {
+        b.iter(|| avro::parse_tag_list_generic(data.as_slice()).expect("should parse"))
+    });
+    group.bench_function("fast", |b| {
+        b.iter(|| avro::parse_tag_list_fast(data.as_slice()).expect("should parse"))
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_tag_decoders);
+criterion_main!(benches);
@@ -0,0 +1,70 @@
+//! Dumps DataItem ids and tag counts of a bundle transaction.
+//!
+//! `cargo run --example dump_bundle -- <transaction_ID>` fetches the bundle from arweave.net,
+//! without arguments the bundled fixture is served by local mock gateway.
+use arweave_dumper::arweave::Client;
+use arweave_rs::crypto::base64::Base64;
+use futures_util::{pin_mut, TryStreamExt};
+use wiremock::{
+    matchers::{method, path},
+    Mock, MockServer, ResponseTemplate,
+};
+
+const FIXTURE_ID: &str = "uYpAeGCj8Xe_J0sKiZ_aJ4Zl1zQLgDH5ia-pqtNLJEA";
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let (client, id, _mock_server) = match std::env::args().nth(1) {
+        Some(id) => (
+            Client::new(arweave_rs::consts::ARWEAVE_BASE_URL)?,
+            id.parse::<Base64>()?,
+            None,
+        ),
+        None => {
+            let mock_server = fixture_gateway().await?;
+            (
+                Client::new(&mock_server.uri())?,
+                FIXTURE_ID.parse::<Base64>()?,
+                Some(mock_server),
+            )
+        }
+    };
+
+    let data_item_stream = client.dump_bundle_stream(&id);
+    pin_mut!(data_item_stream);
+
+    while let Some(data_item) = data_item_stream.try_next().await? {
+        println!(
+            "[{}] tags count: {}",
+            data_item.bundle_id,
+            data_item.tags.len()
+        );
+    }
+    Ok(())
+}
+
+// serves fixture bundle data as a single chunk
+async fn fixture_gateway() -> anyhow::Result<MockServer> {
+    let data = hex::decode(include_str!(
+        "../res/uYpAeGCj8Xe_J0sKiZ_aJ4Zl1zQLgDH5ia-pqtNLJEA_data.hex"
+    ))?;
+    let start_offset = 1000;
+
+    let mock_server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path(format!("/tx/{FIXTURE_ID}/offset")))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "size": data.len().to_string(),
+            "offset": (start_offset + data.len() - 1).to_string(),
+        })))
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path(format!("/chunk/{start_offset}")))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "chunk": Base64(data).to_string(),
+        })))
+        .mount(&mock_server)
+        .await;
+    Ok(mock_server)
+}
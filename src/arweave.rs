@@ -1,24 +1,68 @@
-use std::{collections::HashMap, str::FromStr};
+use std::{
+    collections::{BTreeMap, HashSet},
+    future::Future,
+    io::Read,
+    str::FromStr,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
 
+use anyhow::Context;
 use arweave_rs::{
     crypto::base64::Base64,
     transaction::{tags::Tag, Tx},
 };
 use futures_core::Stream;
-use reqwest::{StatusCode, Url};
+use futures_util::{pin_mut, TryStreamExt};
+use reqwest::{header::HeaderName, redirect, RequestBuilder, Response, StatusCode, Url};
 use serde::Deserialize;
 use serde_aux::prelude::*;
+use tokio::sync::mpsc;
 use tokio_stream::wrappers::ReceiverStream;
-use tokio_util::bytes::Bytes;
+use tokio_util::{bytes::Bytes, io::StreamReader};
+
+use crate::{
+    bundle::{self, DataItem},
+    cache::MetadataCache,
+    chunk_state::{ChunkLog, ChunkStateOptions},
+    error::{self, Error},
+    graphql::{
+        self, BlockFilter, GraphQlRequest, GraphQlResponse, TagFilter, TransactionPage,
+        TransactionQuery,
+    },
+    index::{self, IndexManifest},
+    merkle, metrics,
+};
 
 #[derive(Debug)]
 pub struct TxMetadata {
-    tag_map: HashMap<String, String>,
+    tags: Vec<(String, String)>,
 }
 
 impl TxMetadata {
+    /// First value of the tag.
     pub fn get_tag(&self, name: &str) -> Option<&str> {
-        self.tag_map.get(name).map(|s| s.as_str())
+        self.tags
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Tags by name, first value wins when a name repeats - the same one [`TxMetadata::get_tag`] gives.
+    pub fn tag_map(&self) -> BTreeMap<&str, &str> {
+        let mut tag_map = BTreeMap::new();
+        for (name, value) in self.tags() {
+            tag_map.entry(name).or_insert(value);
+        }
+        tag_map
+    }
+
+    /// All tags in transaction order, names can repeat.
+    pub fn tags(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.tags.iter().map(|(n, v)| (n.as_str(), v.as_str()))
     }
 
     pub fn is_bundle(&self) -> bool {
@@ -44,44 +88,322 @@ pub struct TransactionOffset {
     pub offset: usize,
 }
 
+impl TransactionOffset {
+    // offset points to the last byte of transaction data, gateway could report size past it
+    pub fn start_offset(&self) -> anyhow::Result<usize> {
+        self.offset
+            .checked_add(1)
+            .and_then(|end| end.checked_sub(self.size))
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "Invalid transaction offset {} for data size {}",
+                    self.offset,
+                    self.size
+                )
+            })
+    }
+}
+
 #[derive(Debug, Deserialize)]
 pub struct TransactionChunk {
     pub chunk: Base64,
+    /// Merkle proof of the chunk against transaction `data_root`
+    #[serde(default)]
+    pub data_path: Option<Base64>,
 }
 
 #[derive(Clone)]
 pub struct Client {
     base_url: Url,
     http_client: reqwest::Client,
+    seed_retries: u32,
+    seed_retry_delay: Duration,
+    pending_chunk_timeout: Duration,
+    data_item_url: Option<String>,
+    metadata_cache: Option<MetadataCache>,
+    // set once sniffing is enabled, flag tells whether it already triggered, so that it is logged once
+    sniff_compression: Option<Arc<AtomicBool>>,
+    // retries left to all clones of the client
+    retry_budget: Option<Arc<AtomicU64>>,
+    quiet: bool,
+    // requested and resolved origins of redirects reported so far, shared by clones of the client
+    reported_redirects: Arc<Mutex<HashSet<(String, String)>>>,
+}
+
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+pub const DEFAULT_MAX_REDIRECTS: usize = 10;
+pub const DEFAULT_CHUNK_BUFFER: usize = 2;
+const JSON_CONTENT_TYPE: &str = "application/json";
+const DATA_ITEM_ID_PLACEHOLDER: &str = "{id}";
+pub const DEFAULT_SEED_RETRY_DELAY: Duration = Duration::from_secs(5);
+pub const DEFAULT_PENDING_CHUNK_TIMEOUT: Duration = Duration::from_secs(60);
+const MAX_SEED_RETRY_DELAY: Duration = Duration::from_secs(300);
+
+/// Configures [`Client`], e.g. `Client::builder(url).max_redirects(3).timeout(duration).build()`.
+#[derive(Debug, Clone)]
+pub struct ClientBuilder {
+    api_url: String,
+    max_redirects: usize,
+    follow_redirects: bool,
+    referer: bool,
+    seed_retries: u32,
+    seed_retry_delay: Duration,
+    pending_chunk_timeout: Duration,
+    data_item_url: Option<String>,
+    timeout: Option<Duration>,
+    connect_timeout: Option<Duration>,
+    sniff_compression: bool,
+    max_total_retries: Option<u64>,
+    quiet: bool,
+}
+
+impl ClientBuilder {
+    pub fn new(api_url: &str) -> Self {
+        Self {
+            api_url: api_url.to_string(),
+            max_redirects: DEFAULT_MAX_REDIRECTS,
+            follow_redirects: true,
+            referer: true,
+            seed_retries: 0,
+            seed_retry_delay: DEFAULT_SEED_RETRY_DELAY,
+            pending_chunk_timeout: DEFAULT_PENDING_CHUNK_TIMEOUT,
+            data_item_url: None,
+            timeout: None,
+            connect_timeout: None,
+            sniff_compression: false,
+            max_total_retries: None,
+            quiet: false,
+        }
+    }
+
+    pub fn max_redirects(mut self, max_redirects: usize) -> Self {
+        self.max_redirects = max_redirects;
+        self
+    }
+
+    /// When disabled, redirect responses are reported as [`Error::Http`] instead of being followed.
+    pub fn follow_redirects(mut self, follow_redirects: bool) -> Self {
+        self.follow_redirects = follow_redirects;
+        self
+    }
+
+    /// Whether `Referer` header is sent when following redirects.
+    pub fn referer(mut self, referer: bool) -> Self {
+        self.referer = referer;
+        self
+    }
+
+    /// How many times chunk missing from gateway (404) is retried before giving up with
+    /// [`Error::BundleNotFullySeeded`], freshly posted transactions are often not seeded yet.
+    pub fn seed_retries(mut self, seed_retries: u32) -> Self {
+        self.seed_retries = seed_retries;
+        self
+    }
+
+    /// Delay before the first seed retry, doubled on every next one.
+    pub fn seed_retry_delay(mut self, seed_retry_delay: Duration) -> Self {
+        self.seed_retry_delay = seed_retry_delay;
+        self
+    }
+
+    /// How long chunk answered with 202 (still being seeded) is retried, with the same backoff as seed retries,
+    /// before giving up with [`Error::ChunkPending`]. Zero disables the retries.
+    pub fn pending_chunk_timeout(mut self, pending_chunk_timeout: Duration) -> Self {
+        self.pending_chunk_timeout = pending_chunk_timeout;
+        self
+    }
+
+    /// Endpoint of bundling service serving raw DataItems, e.g. `https://node.example/tx/{id}/raw`,
+    /// `{id}` is replaced by DataItem id. Needed by [`Client::fetch_data_item`].
+    pub fn data_item_url(mut self, url_template: &str) -> Self {
+        self.data_item_url = Some(url_template.to_string());
+        self
+    }
+
+    /// Timeout of every single request, from connecting until response body is read.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    pub fn connect_timeout(mut self, connect_timeout: Duration) -> Self {
+        self.connect_timeout = Some(connect_timeout);
+        self
+    }
+
+    /// Decompress chunk responses starting with gzip magic bytes even without `Content-Encoding` header,
+    /// some misconfigured gateways send them so. JSON body never starts with these bytes.
+    pub fn sniff_compression(mut self, sniff_compression: bool) -> Self {
+        self.sniff_compression = sniff_compression;
+        self
+    }
+
+    /// Cap on seed and pending chunk retries of the client and all its clones together,
+    /// once it is used up retryable failures fail right away.
+    pub fn max_total_retries(mut self, max_total_retries: u64) -> Self {
+        self.max_total_retries = Some(max_total_retries);
+        self
+    }
+
+    /// Suppresses notes the client prints to stderr, e.g. about requests resolved to other urls,
+    /// chunk retries or resumed chunk streams. Warnings are printed anyway.
+    pub fn quiet(mut self, quiet: bool) -> Self {
+        self.quiet = quiet;
+        self
+    }
+
+    pub fn build(self) -> anyhow::Result<Client> {
+        let base_url = normalize_base_url(&self.api_url)?;
+        if let Some(url_template) = &self.data_item_url {
+            if !url_template.contains(DATA_ITEM_ID_PLACEHOLDER) {
+                return Err(anyhow::anyhow!(
+                    "DataItem url {url_template} has no {DATA_ITEM_ID_PLACEHOLDER} placeholder"
+                ));
+            }
+            Url::parse(&url_template.replace(DATA_ITEM_ID_PLACEHOLDER, "id"))
+                .with_context(|| format!("Invalid DataItem url {url_template}"))?;
+        }
+        let redirect_policy = if self.follow_redirects {
+            redirect_policy(&base_url, self.max_redirects)
+        } else {
+            redirect::Policy::none()
+        };
+        let mut http_client = reqwest::ClientBuilder::new()
+            .redirect(redirect_policy)
+            .referer(self.referer);
+        if let Some(timeout) = self.timeout {
+            http_client = http_client.timeout(timeout);
+        }
+        if let Some(connect_timeout) = self.connect_timeout {
+            http_client = http_client.connect_timeout(connect_timeout);
+        }
+        Ok(Client {
+            http_client: http_client.build()?,
+            base_url,
+            seed_retries: self.seed_retries,
+            seed_retry_delay: self.seed_retry_delay,
+            pending_chunk_timeout: self.pending_chunk_timeout,
+            data_item_url: self.data_item_url,
+            metadata_cache: None,
+            sniff_compression: self
+                .sniff_compression
+                .then(|| Arc::new(AtomicBool::new(false))),
+            retry_budget: self
+                .max_total_retries
+                .map(|retries| Arc::new(AtomicU64::new(retries))),
+            quiet: self.quiet,
+            reported_redirects: Arc::default(),
+        })
+    }
 }
 
 impl Client {
     pub fn new(api_url: &str) -> anyhow::Result<Self> {
-        Ok(Self {
-            base_url: Url::from_str(api_url)?,
-            http_client: reqwest::ClientBuilder::new().build()?,
-        })
+        Self::builder(api_url).build()
     }
 
-    async fn fetch_data<D>(&self, url: Url) -> anyhow::Result<D>
+    pub fn builder(api_url: &str) -> ClientBuilder {
+        ClientBuilder::new(api_url)
+    }
+
+    /// Caches up to `capacity` transaction metadata and offset responses, shared by clones of the client.
+    /// Confirmed transactions never change, so cached responses are not revalidated; pending ones are not cached.
+    pub fn with_metadata_cache(mut self, capacity: usize) -> Self {
+        self.metadata_cache = Some(MetadataCache::new(capacity));
+        self
+    }
+
+    async fn get(&self, url: Url) -> anyhow::Result<Response> {
+        self.get_accepting(url, None).await
+    }
+
+    async fn get_accepting(&self, url: Url, accept: Option<&str>) -> anyhow::Result<Response> {
+        let mut request = self.http_client.get(url);
+        if let Some(accept) = accept {
+            request = request.header(reqwest::header::ACCEPT, accept);
+        }
+        self.send(request).await
+    }
+
+    // every request goes through here, so that gateway resolving any of them to some other url is reported
+    async fn send(&self, request: RequestBuilder) -> anyhow::Result<Response> {
+        let (http_client, request) = request.build_split();
+        let request = request?;
+        let url = request.url().clone();
+        let res = send(RequestBuilder::from_parts(http_client, request)).await?;
+        if res.url() != &url {
+            self.report_resolved(&url, res.url());
+        }
+        Ok(res)
+    }
+
+    // once per requested and resolved origin, every chunk request would be reported otherwise
+    fn report_resolved(&self, url: &Url, resolved: &Url) {
+        if self.quiet {
+            return;
+        }
+        let origins = (
+            url.origin().ascii_serialization(),
+            resolved.origin().ascii_serialization(),
+        );
+        let first = self
+            .reported_redirects
+            .lock()
+            .expect("should not be poisoned")
+            .insert(origins);
+        if first {
+            eprintln!("{url} resolved to {resolved}, following such requests are not reported");
+        }
+    }
+
+    async fn fetch_data<D>(&self, url: Url, accept: Option<&str>) -> anyhow::Result<D>
     where
         D: FromStr,
         D::Err: std::error::Error + Send + Sync + 'static,
     {
-        let res = self.http_client.get(url).send().await?.error_for_status()?;
+        let res = self.get_accepting(url, accept).await?;
 
         if res.status() == StatusCode::ACCEPTED {
-            return Err(anyhow::anyhow!("Pending"));
+            return Err(Error::Pending.into());
+        }
+        if accept == Some(JSON_CONTENT_TYPE) {
+            check_json_content_type(&res)?;
         }
 
         let val = D::from_str(&res.text().await?)?;
         Ok(val)
     }
 
-    pub async fn fetch_transaction(&self, id: &Base64) -> anyhow::Result<TxMetadata> {
-        let tx: Tx = self
-            .fetch_data(self.base_url.join(&format!("tx/{id}"))?)
+    // JSON body of immutable metadata response, from cache if enabled.
+    // Some gateways answer with HTML page unless JSON is asked for explicitly
+    async fn fetch_metadata(&self, url: Url) -> anyhow::Result<String> {
+        if let Some(body) = self
+            .metadata_cache
+            .as_ref()
+            .and_then(|cache| cache.get(url.as_str()))
+        {
+            return Ok(body);
+        }
+        let res = self
+            .get_accepting(url.clone(), Some(JSON_CONTENT_TYPE))
             .await?;
+        if res.status() == StatusCode::ACCEPTED {
+            return Err(Error::Pending.into());
+        }
+        check_json_content_type(&res)?;
+        let body = res.text().await?;
+        if let Some(cache) = &self.metadata_cache {
+            cache.insert(url.to_string(), body.clone());
+        }
+        Ok(body)
+    }
+
+    pub async fn fetch_transaction(&self, id: &Base64) -> anyhow::Result<TxMetadata> {
+        let tx = Tx::from_str(
+            &self
+                .fetch_metadata(self.base_url.join(&format!("tx/{id}"))?)
+                .await?,
+        )?;
 
         let tags = tx
             .tags
@@ -97,58 +419,1699 @@ impl Client {
             )
             .collect::<Result<Vec<_>, _>>()?;
 
-        let tag_map = HashMap::from_iter(tags);
-        Ok(TxMetadata { tag_map })
+        Ok(TxMetadata { tags })
     }
 
     pub async fn fetch_transaction_data(&self, id: &Base64) -> anyhow::Result<Base64> {
-        self.fetch_data(self.base_url.join(&format!("tx/{id}/data"))?)
-            .await
+        let data: Base64 = self
+            .fetch_data(self.base_url.join(&format!("tx/{id}/data"))?, None)
+            .await?;
+        metrics::BYTES_FETCHED.add(data.0.len() as u64);
+        Ok(data)
+    }
+
+    /// Fetches transaction data as reconstructed and served by the gateway itself:
+    /// `/tx/{id}/data.{ext}` if extension is given, bare `/{id}` otherwise.
+    pub async fn fetch_served_data(&self, id: &Base64, ext: Option<&str>) -> anyhow::Result<Bytes> {
+        let path = match ext {
+            Some(ext) => format!("tx/{id}/data.{ext}"),
+            None => id.to_string(),
+        };
+        let resp = self.get(self.base_url.join(&path)?).await?;
+        let data = resp.bytes().await?;
+        metrics::BYTES_FETCHED.add(data.len() as u64);
+        Ok(data)
     }
 
     pub async fn fetch_transaction_offset(&self, id: &Base64) -> anyhow::Result<TransactionOffset> {
-        let resp = self
-            .http_client
-            .get(self.base_url.join(&format!("tx/{id}/offset"))?)
-            .send()
-            .await?
-            .error_for_status()?;
+        let body = self
+            .fetch_metadata(self.base_url.join(&format!("tx/{id}/offset"))?)
+            .await?;
+        Ok(serde_json::from_str(&body)?)
+    }
 
-        Ok(resp.json().await?)
+    /// Fee in winston for storing `bytes` of transaction data, as quoted by gateway `/price/{bytes}` endpoint.
+    pub async fn fetch_price(&self, bytes: u64) -> anyhow::Result<u64> {
+        let res = self
+            .get(self.base_url.join(&format!("price/{bytes}"))?)
+            .await?;
+        let price = res.text().await?;
+        price
+            .trim()
+            .parse()
+            .with_context(|| format!("Invalid price {price} for {bytes} bytes"))
     }
 
     pub async fn fetch_chunk_data(&self, offset: usize) -> anyhow::Result<TransactionChunk> {
         let resp = self
-            .http_client
             .get(self.base_url.join(&format!("chunk/{offset}"))?)
-            .send()
+            .await?;
+        if resp.status() == StatusCode::ACCEPTED {
+            return Err(Error::ChunkPending { offset }.into());
+        }
+
+        let chunk: TransactionChunk = match &self.sniff_compression {
+            Some(triggered) => {
+                let body = resp.bytes().await?;
+                if !body.starts_with(&GZIP_MAGIC) {
+                    serde_json::from_slice(&body)?
+                } else {
+                    if !triggered.swap(true, Ordering::Relaxed) && !self.quiet {
+                        eprintln!("NOTE: chunk at offset {offset} is gzip compressed without Content-Encoding header, decompressing it and following ones");
+                    }
+                    let mut decompressed = vec![];
+                    flate2::read::GzDecoder::new(body.as_ref())
+                        .read_to_end(&mut decompressed)
+                        .with_context(|| format!("Chunk at offset {offset} gzip decompress"))?;
+                    serde_json::from_slice(&decompressed)?
+                }
+            }
+            None => resp.json().await?,
+        };
+        metrics::BYTES_FETCHED.add(chunk.chunk.0.len() as u64);
+        Ok(chunk)
+    }
+
+    /// Reads child bundle references of index transaction, either from its path manifest data
+    /// or from its `tag_name` tags.
+    pub async fn fetch_index_manifest(
+        &self,
+        id: &Base64,
+        tag_name: &str,
+    ) -> anyhow::Result<IndexManifest> {
+        let tx = self.fetch_transaction(id).await?;
+        let manifest = if tx.get_tag("Content-Type") == Some(index::MANIFEST_CONTENT_TYPE) {
+            let data = self.fetch_served_data(id, None).await?;
+            IndexManifest::from_path_manifest(&data).context("path manifest parse")?
+        } else {
+            IndexManifest::from_tags(tx.tags(), tag_name)?
+        };
+
+        if manifest.ids.is_empty() {
+            return Err(anyhow::anyhow!(
+                "Transaction {id} is not an index: no path manifest nor {tag_name} tags"
+            ));
+        }
+        Ok(manifest)
+    }
+
+    /// Reads number of DataItems in the bundle from the first data chunk only.
+    pub async fn fetch_bundle_item_count(&self, id: &Base64) -> anyhow::Result<u128> {
+        let tx_offset_data = self.fetch_transaction_offset(id).await?;
+        let first_chunk = self
+            .fetch_chunk_data(tx_offset_data.start_offset()?)
             .await?
-            .error_for_status()?;
+            .chunk;
+        bundle::read_bundle_item_count(first_chunk.0.as_slice()).await
+    }
+
+    /// Reads number of DataItems from the first 32 bytes of served transaction data by `Range` request.
+    /// Gateway ignoring the range is not read any further, [`Self::fetch_bundle_item_count`] is used instead.
+    pub async fn peek_item_count(&self, id: &Base64) -> anyhow::Result<u128> {
+        // u256 item count is the only thing needed
+        const ITEM_COUNT_SIZE: usize = 32;
+        let url = self.base_url.join(&id.to_string())?;
+        let resp = self
+            .send(self.http_client.get(url).header(
+                reqwest::header::RANGE,
+                format!("bytes=0-{}", ITEM_COUNT_SIZE - 1),
+            ))
+            .await?;
+        if resp.status() != StatusCode::PARTIAL_CONTENT {
+            return self.fetch_bundle_item_count(id).await;
+        }
+        let data = resp.bytes().await?;
+        metrics::BYTES_FETCHED.add(data.len() as u64);
+        bundle::read_bundle_item_count(data.as_ref()).await
+    }
+
+    /// Lists DataItem ids of the bundle reading only its header and DataItem table.
+    pub async fn bundle_item_ids(&self, id: &Base64) -> anyhow::Result<Vec<Base64>> {
+        let chunk_stream = self
+            .transaction_data_chunk_stream(id)
+            .map_err(error::into_io_error);
+        let stream_reader = StreamReader::new(chunk_stream);
+        pin_mut!(stream_reader);
+
+        bundle::read_bundle_item_ids(stream_reader).await
+    }
+
+    /// Fetches single DataItem of the bundle: DataItem table is read from the first chunks,
+    /// DataItem itself by range request of served transaction data.
+    pub async fn fetch_bundle_item(&self, id: &Base64, index: usize) -> anyhow::Result<DataItem> {
+        let chunk_stream = self
+            .transaction_data_chunk_stream(id)
+            .map_err(error::into_io_error);
+        let stream_reader = StreamReader::new(chunk_stream);
+        pin_mut!(stream_reader);
+        let data_items_table = bundle::read_bundle_item_table(stream_reader).await?;
+
+        let (size, entry_id) = data_items_table.get(index).ok_or_else(|| {
+            anyhow::anyhow!(
+                "DataItem index {index} is out of range, bundle has {} DataItems",
+                data_items_table.len()
+            )
+        })?;
+        let start = data_items_table[..index].iter().fold(
+            32 + 64 * data_items_table.len() as u128,
+            |acc, (size, _)| acc.saturating_add(*size),
+        );
+        let start = usize::try_from(start)?;
+        let size = usize::try_from(*size)?;
+
+        let data = self.fetch_served_range(id, start, size).await?;
+        let data_item = bundle::read_data_item(data.as_ref())
+            .await
+            .context(format!("DataItem {index} (size: {size}) read"))?;
+        if data_item.bundle_id != *entry_id {
+            return Err(anyhow::anyhow!(
+                "DataItem {index} id mismatch: read {} but bundle table has {entry_id}",
+                data_item.bundle_id
+            ));
+        }
+        Ok(data_item)
+    }
 
-        Ok(resp.json().await?)
+    /// Fetches single DataItem by its own id from bundling service endpoint configured by
+    /// [`ClientBuilder::data_item_url`], without any bundle around it.
+    pub async fn fetch_data_item(&self, id: &Base64) -> anyhow::Result<DataItem> {
+        let url_template = self.data_item_url.as_deref().ok_or_else(|| {
+            anyhow::anyhow!("DataItem url is not configured, see ClientBuilder::data_item_url")
+        })?;
+        let url = Url::parse(&url_template.replace(DATA_ITEM_ID_PLACEHOLDER, &id.to_string()))?;
+        let data = self.get(url).await?.bytes().await?;
+        metrics::BYTES_FETCHED.add(data.len() as u64);
+
+        let data_item = bundle::read_data_item(data.as_ref())
+            .await
+            .context(format!("DataItem {id} (size: {}) read", data.len()))?;
+        if data_item.bundle_id != *id {
+            return Err(anyhow::anyhow!(
+                "DataItem id mismatch: requested {id} but signature gives {}",
+                data_item.bundle_id
+            ));
+        }
+        Ok(data_item)
+    }
+
+    // `size` bytes of served transaction data starting at `start`
+    async fn fetch_served_range(
+        &self,
+        id: &Base64,
+        start: usize,
+        size: usize,
+    ) -> anyhow::Result<Bytes> {
+        if size == 0 {
+            return Ok(Bytes::new());
+        }
+        let url = self.base_url.join(&id.to_string())?;
+        let resp = self
+            .send(self.http_client.get(url).header(
+                reqwest::header::RANGE,
+                format!("bytes={start}-{}", start + size - 1),
+            ))
+            .await?;
+        let partial = resp.status() == StatusCode::PARTIAL_CONTENT;
+        let data = resp.bytes().await?;
+        metrics::BYTES_FETCHED.add(data.len() as u64);
+
+        // gateway ignoring range sends whole data
+        let data = if partial {
+            data
+        } else {
+            data.slice(start.min(data.len())..(start + size).min(data.len()))
+        };
+        if data.len() != size {
+            return Err(anyhow::anyhow!(
+                "Expected {size} bytes of transaction {id} data at offset {start}, got {}",
+                data.len()
+            ));
+        }
+        Ok(data)
+    }
+
+    /// Streams DataItems of the bundle transaction as its data chunks arrive.
+    pub fn dump_bundle_stream(&self, id: &Base64) -> impl Stream<Item = anyhow::Result<DataItem>> {
+        let chunk_stream = self
+            .transaction_data_chunk_stream(id)
+            .map_err(error::into_io_error);
+        bundle::ans104_bundle_data_item_stream(StreamReader::new(Box::pin(chunk_stream)))
+    }
+
+    /// Streams all data chunks of the bundle transaction verifying each of them against transaction `data_root`
+    /// and checks that chunks, bundle header and DataItem table add up to declared data size.
+    /// Nothing is written anywhere, `Ok(false)` means that bundle data is not intact.
+    pub async fn verify_bundle(&self, id: &Base64) -> anyhow::Result<bool> {
+        let tx: Tx = self
+            .fetch_data(
+                self.base_url.join(&format!("tx/{id}"))?,
+                Some(JSON_CONTENT_TYPE),
+            )
+            .await?;
+        let data_size = usize::try_from(tx.data_size)?;
+        let tx_offset_data = self.fetch_transaction_offset(id).await?;
+        if tx_offset_data.size != data_size {
+            return Ok(false);
+        }
+
+        let start_offset = tx_offset_data.start_offset()?;
+        // chunks are kept only until bundle header and DataItem table are complete
+        let mut header = vec![];
+        let mut header_size = None;
+        let mut verified_size = 0;
+        while verified_size < data_size {
+            let chunk_offset = start_offset + verified_size;
+            let TransactionChunk { chunk, data_path } = self.fetch_chunk_data(chunk_offset).await?;
+            let data_path = data_path.ok_or_else(|| {
+                anyhow::anyhow!("Chunk at offset {chunk_offset} has no data_path")
+            })?;
+            if chunk.0.is_empty()
+                || !merkle::verify_chunk(
+                    &tx.data_root.0,
+                    verified_size,
+                    data_size,
+                    &chunk.0,
+                    &data_path.0,
+                )
+            {
+                return Ok(false);
+            }
+            verified_size += chunk.0.len();
+
+            if header_size.is_none_or(|size| (header.len() as u128) < size) {
+                header.extend_from_slice(&chunk.0);
+            }
+            if header_size.is_none() && header.len() >= 32 {
+                let Ok(item_count) = bundle::read_bundle_item_count(&header[..32]).await else {
+                    return Ok(false);
+                };
+                header_size = Some(item_count.saturating_mul(64).saturating_add(32));
+            }
+        }
+
+        match header_size {
+            Some(size) if size <= data_size as u128 => {
+                bundle::bundle_size_matches(header.as_slice(), data_size).await
+            }
+            _ => Ok(false),
+        }
+    }
+
+    pub async fn query_transactions(
+        &self,
+        query: &TransactionQuery,
+    ) -> anyhow::Result<TransactionPage> {
+        let url = self.base_url.join("graphql")?;
+        let resp = self
+            .send(
+                self.http_client
+                    .post(url)
+                    .json(&GraphQlRequest::transactions(query)),
+            )
+            .await?;
+
+        let GraphQlResponse { data, errors } = resp.json().await?;
+        if let Some(error) = errors.first() {
+            return Err(anyhow::anyhow!("GraphQL query failed: {}", error.message));
+        }
+        data.map(|d| d.transactions)
+            .ok_or_else(|| anyhow::anyhow!("GraphQL response without data"))
+    }
+
+    /// Scans up to `max_pages` of bundle transactions (optionally of given owners) for ids starting with `prefix`.
+    /// GraphQL has no prefix filter for ids, so matching is done on our side.
+    pub async fn find_bundles_by_id_prefix(
+        &self,
+        prefix: &str,
+        owners: Vec<String>,
+        max_pages: usize,
+    ) -> anyhow::Result<Vec<Base64>> {
+        let mut query = TransactionQuery {
+            owners,
+            tags: vec![TagFilter::bundles()],
+            ..Default::default()
+        };
+
+        let mut matches = vec![];
+        for _ in 0..max_pages {
+            let page = self.query_transactions(&query).await?;
+            matches.extend(
+                page.edges
+                    .iter()
+                    .map(|edge| &edge.node.id)
+                    .filter(|id| id.to_string().starts_with(prefix))
+                    .cloned(),
+            );
+            if !page.page_info.has_next_page {
+                break;
+            }
+            query.after = page.last_cursor().map(str::to_string);
+        }
+        Ok(matches)
+    }
+
+    /// Keeps transactions of `ids` mined in `block` heights, in the order of `ids`.
+    pub async fn filter_by_block_height(
+        &self,
+        ids: &[Base64],
+        block: BlockFilter,
+    ) -> anyhow::Result<Vec<Base64>> {
+        let mut mined = HashSet::new();
+        for ids in ids.chunks(graphql::MAX_PAGE_SIZE) {
+            let mut query = TransactionQuery {
+                ids: ids.iter().map(ToString::to_string).collect(),
+                block: Some(block),
+                ..Default::default()
+            };
+            loop {
+                let page = self.query_transactions(&query).await?;
+                mined.extend(page.edges.iter().map(|edge| edge.node.id.to_string()));
+                if !page.page_info.has_next_page {
+                    break;
+                }
+                query.after = page.last_cursor().map(str::to_string);
+            }
+        }
+        Ok(ids
+            .iter()
+            .filter(|id| mined.contains(&id.to_string()))
+            .cloned()
+            .collect())
     }
 
     pub fn transaction_data_chunk_stream(
         &self,
         id: &Base64,
     ) -> impl Stream<Item = anyhow::Result<Bytes>> {
-        let (tx, rx) = tokio::sync::mpsc::channel(2);
+        self.transaction_data_chunk_stream_with_buffer(id, DEFAULT_CHUNK_BUFFER)
+    }
 
+    /// Chunks are fetched by separate task which can run ahead of the consumer by `chunk_buffer` chunks.
+    pub fn transaction_data_chunk_stream_with_buffer(
+        &self,
+        id: &Base64,
+        chunk_buffer: usize,
+    ) -> impl Stream<Item = anyhow::Result<Bytes>> {
+        let client = self.clone();
+        let id = id.clone();
+        self.chunk_stream(
+            async move { client.fetch_transaction_offset(&id).await },
+            chunk_buffer,
+            None,
+        )
+    }
+
+    /// Same as [`Client::transaction_data_chunk_stream_with_buffer`], but fetched data and offset of the next chunk
+    /// are persisted, so that stream interrupted by a failure can be resumed, see [`ChunkStateOptions`].
+    pub fn transaction_data_chunk_stream_with_state(
+        &self,
+        id: &Base64,
+        chunk_buffer: usize,
+        state: ChunkStateOptions,
+    ) -> impl Stream<Item = anyhow::Result<Bytes>> {
         let client = self.clone();
         let id = id.clone();
+        self.chunk_stream(
+            async move { client.fetch_transaction_offset(&id).await },
+            chunk_buffer,
+            Some(state),
+        )
+    }
+
+    /// Same as [`Client::transaction_data_chunk_stream`], but with already known `offset` and `size`
+    /// of transaction data (as returned by `/tx/{id}/offset`, `offset` points to the last byte of data)
+    /// instead of fetching them.
+    pub fn transaction_data_chunk_stream_with_offset(
+        &self,
+        offset: usize,
+        size: usize,
+    ) -> impl Stream<Item = anyhow::Result<Bytes>> {
+        self.chunk_stream(
+            async move { Ok(TransactionOffset { size, offset }) },
+            DEFAULT_CHUNK_BUFFER,
+            None,
+        )
+    }
+
+    fn chunk_stream<F>(
+        &self,
+        tx_offset: F,
+        chunk_buffer: usize,
+        state: Option<ChunkStateOptions>,
+    ) -> impl Stream<Item = anyhow::Result<Bytes>>
+    where
+        F: Future<Output = anyhow::Result<TransactionOffset>> + Send + 'static,
+    {
+        let (tx, rx) = mpsc::channel(chunk_buffer);
+
+        let client = self.clone();
         tokio::spawn(async move {
-            // inspired by <https://github.com/everFinance/goar/blob/main/client.go#L612>
-            let tx_offset_data = client.fetch_transaction_offset(&id).await?;
-            let mut chunk_offset = tx_offset_data.offset - tx_offset_data.size + 1;
-            while chunk_offset < tx_offset_data.offset {
-                let data = client.fetch_chunk_data(chunk_offset).await?.chunk;
-                chunk_offset += data.0.len();
-                // TODO we should pass actual errors here
-                tx.send(Ok(Bytes::from(data.0))).await?;
-            }
-            anyhow::Result::<()>::Ok(())
+            if let Err(err) = client.send_chunks(tx_offset, state, &tx).await {
+                // consumer may be gone already, then there is nobody to report to
+                let _ = tx.send(Err(err)).await;
+            }
         });
 
         ReceiverStream::new(rx)
     }
+
+    async fn send_chunks(
+        &self,
+        tx_offset: impl Future<Output = anyhow::Result<TransactionOffset>>,
+        state: Option<ChunkStateOptions>,
+        tx: &mpsc::Sender<anyhow::Result<Bytes>>,
+    ) -> anyhow::Result<()> {
+        // inspired by <https://github.com/everFinance/goar/blob/main/client.go#L612>
+        let tx_offset_data = tx_offset.await?;
+        let start_offset = tx_offset_data.start_offset()?;
+        let mut chunk_offset = start_offset;
+        let mut chunk_log = match &state {
+            Some(state) => {
+                let mut chunk_log =
+                    ChunkLog::open(state, start_offset, tx_offset_data.offset, self.quiet).await?;
+                chunk_log.replay(tx).await?;
+                chunk_offset = chunk_log.chunk_offset();
+                Some(chunk_log)
+            }
+            None => None,
+        };
+        while chunk_offset < tx_offset_data.offset {
+            let data = match self.fetch_seeded_chunk_data(chunk_offset).await {
+                Ok(chunk) => chunk.chunk,
+                Err(err) => {
+                    // resumed stream starts right at the failed chunk
+                    if let Some(chunk_log) = &mut chunk_log {
+                        chunk_log.persist().await?;
+                    }
+                    return Err(match is_not_found(&err) {
+                        true => Error::BundleNotFullySeeded {
+                            retrieved: chunk_offset - start_offset,
+                            size: tx_offset_data.size,
+                        }
+                        .into(),
+                        false => err,
+                    });
+                }
+            };
+            chunk_offset += data.0.len();
+            if let Some(chunk_log) = &mut chunk_log {
+                chunk_log.append(&data.0).await?;
+            }
+            tx.send(Ok(Bytes::from(data.0))).await?;
+        }
+        if let Some(chunk_log) = chunk_log {
+            chunk_log.finish().await?;
+        }
+        Ok(())
+    }
+
+    // missing or pending chunk is retried with backoff as it may be not seeded yet
+    async fn fetch_seeded_chunk_data(&self, offset: usize) -> anyhow::Result<TransactionChunk> {
+        let mut delay = self.seed_retry_delay;
+        let mut retries = 0;
+        let pending_deadline = tokio::time::Instant::now() + self.pending_chunk_timeout;
+        loop {
+            match self.fetch_chunk_data(offset).await {
+                Err(err)
+                    if is_not_found(&err)
+                        && retries < self.seed_retries
+                        && self.take_retry(offset) =>
+                {
+                    retries += 1;
+                    metrics::RETRIES.inc();
+                    if !self.quiet {
+                        eprintln!(
+                            "Chunk at offset {offset} is not seeded yet, retry {retries}/{} in {delay:?}",
+                            self.seed_retries
+                        );
+                    }
+                    tokio::time::sleep(delay).await;
+                    delay = next_seed_retry_delay(delay);
+                }
+                Err(err)
+                    if is_chunk_pending(&err)
+                        && tokio::time::Instant::now() < pending_deadline
+                        && self.take_retry(offset) =>
+                {
+                    let wait = delay.min(pending_deadline - tokio::time::Instant::now());
+                    metrics::RETRIES.inc();
+                    if !self.quiet {
+                        eprintln!("Chunk at offset {offset} is pending, retry in {wait:?}");
+                    }
+                    tokio::time::sleep(wait).await;
+                    delay = next_seed_retry_delay(delay);
+                }
+                res => return res,
+            }
+        }
+    }
+
+    // whether shared retry budget, if any, allows one more retry of chunk at offset
+    fn take_retry(&self, offset: usize) -> bool {
+        let Some(retry_budget) = &self.retry_budget else {
+            return true;
+        };
+        let allowed = retry_budget
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |left| {
+                left.checked_sub(1)
+            })
+            .is_ok();
+        if !allowed && !self.quiet {
+            eprintln!("Chunk at offset {offset} is not retried, retry budget is exhausted");
+        }
+        allowed
+    }
+}
+
+// missing content type is given the benefit of the doubt
+fn check_json_content_type(res: &Response) -> Result<(), Error> {
+    match res
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .map(|v| v.to_str().unwrap_or_default())
+    {
+        Some(content_type) if !content_type.contains("json") => Err(Error::UnexpectedContentType {
+            url: res.url().to_string(),
+            content_type: content_type.to_string(),
+        }),
+        _ => Ok(()),
+    }
+}
+
+fn is_not_found(err: &anyhow::Error) -> bool {
+    matches!(
+        err.downcast_ref::<Error>(),
+        Some(Error::Http { status: 404, .. })
+    )
+}
+
+fn is_chunk_pending(err: &anyhow::Error) -> bool {
+    matches!(
+        err.downcast_ref::<Error>(),
+        Some(Error::ChunkPending { .. })
+    )
+}
+
+// doubled up to a cap, configured delay above the cap is kept as is
+fn next_seed_retry_delay(delay: Duration) -> Duration {
+    delay.max(delay.saturating_mul(2).min(MAX_SEED_RETRY_DELAY))
+}
+
+// failed requests are counted in metrics
+async fn send(request: RequestBuilder) -> anyhow::Result<Response> {
+    let res = async { anyhow::Ok(check_status(request.send().await?)?) }.await;
+    if res.is_err() {
+        metrics::FETCH_ERRORS.inc();
+    }
+    res
+}
+
+fn check_status(res: Response) -> Result<Response, Error> {
+    let status = res.status();
+    // redirects which are left unfollowed are errors too
+    if !status.is_redirection() && !status.is_client_error() && !status.is_server_error() {
+        return Ok(res);
+    }
+
+    let header = |name| {
+        res.headers()
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string)
+    };
+    Err(Error::Http {
+        status: status.as_u16(),
+        url: res.url().to_string(),
+        // only delay in seconds is supported, not HTTP date
+        retry_after: header(reqwest::header::RETRY_AFTER)
+            .and_then(|v| v.parse().ok())
+            .map(Duration::from_secs),
+        trace_id: header(HeaderName::from_static("x-trace-id")),
+    })
+}
+
+/// Parses transaction (or DataItem) ID, tolerating standard base64 alphabet and padding
+/// which often sneak in by copy-pasting.
+pub fn parse_transaction_id(id: &str) -> Result<Base64, String> {
+    let normalized = id
+        .trim()
+        .trim_end_matches('=')
+        .replace('+', "-")
+        .replace('/', "_");
+    match Base64::from_str(&normalized) {
+        Ok(parsed) if parsed.0.len() == 32 => Ok(parsed),
+        _ => Err(format!(
+            "invalid ID {id:?}, expected 43 characters of URL-safe base64 (A-Z, a-z, 0-9, '-' and '_')"
+        )),
+    }
+}
+
+/// Parses newline separated IDs, blank lines and `#` comments are skipped, repeated IDs are kept once.
+/// Fails listing line numbers of all malformed entries.
+pub fn parse_transaction_id_list(text: &str) -> anyhow::Result<Vec<Base64>> {
+    let mut ids = vec![];
+    let mut seen = HashSet::new();
+    let mut errors = vec![];
+    for (idx, line) in text.lines().enumerate() {
+        let entry = line.split('#').next().unwrap_or_default().trim();
+        if entry.is_empty() {
+            continue;
+        }
+        match parse_transaction_id(entry) {
+            Ok(id) => {
+                if seen.insert(id.0.clone()) {
+                    ids.push(id);
+                }
+            }
+            Err(err) => errors.push(format!("line {}: {err}", idx + 1)),
+        }
+    }
+    if !errors.is_empty() {
+        return Err(anyhow::anyhow!("{}", errors.join("\n")));
+    }
+    Ok(ids)
+}
+
+// makes sure base url ends with slash, otherwise joining relative paths would drop its last path segment
+fn normalize_base_url(api_url: &str) -> anyhow::Result<Url> {
+    let mut base_url = Url::from_str(api_url)?;
+    if !base_url.path().ends_with('/') {
+        let path = format!("{}/", base_url.path());
+        base_url.set_path(&path);
+    }
+    Ok(base_url)
+}
+
+// follows at most `max_redirects` redirects and warns when gateway sends us to some other host
+fn redirect_policy(base_url: &Url, max_redirects: usize) -> redirect::Policy {
+    let expected_host = base_url.host_str().map(str::to_string);
+    redirect::Policy::custom(move |attempt| {
+        if attempt.previous().len() > max_redirects {
+            return attempt.error(format!("too many redirects (max: {max_redirects})"));
+        }
+        let host = attempt.url().host_str();
+        if host != expected_host.as_deref() {
+            eprintln!(
+                "WARNING: gateway redirected {} to unexpected host: {}",
+                attempt
+                    .previous()
+                    .last()
+                    .map(Url::as_str)
+                    .unwrap_or_default(),
+                host.unwrap_or_default(),
+            );
+        }
+        attempt.follow()
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Write;
+
+    use wiremock::{
+        matchers::{body_partial_json, header, method, path},
+        Mock, MockServer, ResponseTemplate,
+    };
+
+    use super::*;
+    use crate::error::ErrorKind;
+
+    #[tokio::test]
+    async fn test_start_offset_rejects_size_past_offset() {
+        let offset = |size, offset| TransactionOffset { size, offset };
+        assert_eq!(offset(10, 9).start_offset().expect("should be valid"), 0);
+        assert_eq!(offset(0, 9).start_offset().expect("should be valid"), 10);
+        offset(11, 9).start_offset().expect_err("should fail");
+        offset(0, usize::MAX)
+            .start_offset()
+            .expect_err("should fail");
+
+        let client = Client::new("http://localhost:1984").expect("should not fail");
+        let mut stream = Box::pin(client.transaction_data_chunk_stream_with_offset(9, 11));
+        stream.try_next().await.expect_err("should fail");
+    }
+
+    #[test]
+    fn test_seed_retry_delay_is_capped() {
+        let mut delay = Duration::from_secs(5);
+        for _ in 0..100 {
+            delay = next_seed_retry_delay(delay);
+        }
+        assert_eq!(delay, MAX_SEED_RETRY_DELAY);
+        assert_eq!(
+            next_seed_retry_delay(Duration::from_millis(1)),
+            Duration::from_millis(2)
+        );
+        assert_eq!(
+            next_seed_retry_delay(Duration::from_secs(3600)),
+            Duration::from_secs(3600)
+        );
+        assert_eq!(next_seed_retry_delay(Duration::MAX), Duration::MAX);
+    }
+
+    #[test]
+    fn test_parse_transaction_id_normalizes_standard_base64() {
+        let id = "uYpAeGCj8Xe_J0sKiZ_aJ4Zl1zQLgDH5ia-pqtNLJEA";
+        let expected = Base64::from_str(id).expect("should parse");
+
+        let parsed = parse_transaction_id("uYpAeGCj8Xe/J0sKiZ/aJ4Zl1zQLgDH5ia+pqtNLJEA=")
+            .expect("should parse");
+        assert_eq!(parsed, expected);
+        assert_eq!(parse_transaction_id(id).expect("should parse"), expected);
+    }
+
+    #[test]
+    fn test_parse_transaction_id_rejects_invalid() {
+        let err = parse_transaction_id("not an id").expect_err("should fail");
+        assert!(err.contains("URL-safe base64"));
+        // valid base64, but not 32 bytes
+        parse_transaction_id("AAAA").expect_err("should fail");
+    }
+
+    #[test]
+    fn test_parse_transaction_id_list() {
+        let text = "# bundles to archive\n\
+            uYpAeGCj8Xe_J0sKiZ_aJ4Zl1zQLgDH5ia-pqtNLJEA\n\
+            \n\
+            o0le1MwgKBVIrh3fqJnWCGNa4N0rDd2WDm15jjGIvBo  # second one\n\
+            uYpAeGCj8Xe_J0sKiZ_aJ4Zl1zQLgDH5ia-pqtNLJEA\n";
+        let ids = parse_transaction_id_list(text).expect("should parse");
+        assert_eq!(
+            ids.iter().map(ToString::to_string).collect::<Vec<_>>(),
+            vec![
+                "uYpAeGCj8Xe_J0sKiZ_aJ4Zl1zQLgDH5ia-pqtNLJEA",
+                "o0le1MwgKBVIrh3fqJnWCGNa4N0rDd2WDm15jjGIvBo"
+            ]
+        );
+
+        let err = parse_transaction_id_list(
+            "uYpAeGCj8Xe_J0sKiZ_aJ4Zl1zQLgDH5ia-pqtNLJEA\nAAAA\n\nbad id",
+        )
+        .expect_err("should fail");
+        let message = err.to_string();
+        assert!(
+            message.starts_with("line 2: invalid ID \"AAAA\""),
+            "{message}"
+        );
+        assert!(
+            message.contains("line 4: invalid ID \"bad id\""),
+            "{message}"
+        );
+    }
+
+    #[test]
+    fn test_base_url_with_trailing_slash() {
+        let base_url = normalize_base_url("https://arweave.net/").expect("should parse");
+        assert_eq!(
+            base_url.join("tx/abc").expect("should join").as_str(),
+            "https://arweave.net/tx/abc"
+        );
+
+        let base_url = normalize_base_url("https://arweave.net").expect("should parse");
+        assert_eq!(
+            base_url.join("tx/abc").expect("should join").as_str(),
+            "https://arweave.net/tx/abc"
+        );
+    }
+
+    #[test]
+    fn test_base_url_with_base_path() {
+        for api_url in ["https://host/arweave", "https://host/arweave/"] {
+            let base_url = normalize_base_url(api_url).expect("should parse");
+            assert_eq!(
+                base_url
+                    .join("tx/abc/offset")
+                    .expect("should join")
+                    .as_str(),
+                "https://host/arweave/tx/abc/offset"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_http_error_carries_status_and_headers() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/chunk/100"))
+            .respond_with(
+                ResponseTemplate::new(503)
+                    .insert_header("retry-after", "5")
+                    .insert_header("x-trace-id", "trace-1"),
+            )
+            .mount(&mock_server)
+            .await;
+
+        let client = Client::new(&mock_server.uri()).expect("should not fail");
+        let err = client.fetch_chunk_data(100).await.expect_err("should fail");
+
+        match err.downcast_ref::<Error>() {
+            Some(Error::Http {
+                status,
+                retry_after,
+                trace_id,
+                ..
+            }) => {
+                assert_eq!(*status, 503);
+                assert_eq!(*retry_after, Some(Duration::from_secs(5)));
+                assert_eq!(trace_id.as_deref(), Some("trace-1"));
+            }
+            _ => panic!("unexpected error: {err}"),
+        }
+        assert_eq!(ErrorKind::of(&err), ErrorKind::Http);
+    }
+
+    async fn mock_redirect() -> MockServer {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/chunk/100"))
+            .respond_with(
+                ResponseTemplate::new(302)
+                    .insert_header("location", format!("{}/chunk/200", mock_server.uri())),
+            )
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/chunk/200"))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_json(serde_json::json!({ "chunk": "" })),
+            )
+            .mount(&mock_server)
+            .await;
+        mock_server
+    }
+
+    #[tokio::test]
+    async fn test_builder_referer() {
+        for referer in [true, false] {
+            let mock_server = mock_redirect().await;
+            let client = Client::builder(&mock_server.uri())
+                .referer(referer)
+                .build()
+                .expect("should not fail");
+            client.fetch_chunk_data(100).await.expect("should follow");
+
+            let requests = mock_server
+                .received_requests()
+                .await
+                .expect("should be recorded");
+            let redirected = requests
+                .iter()
+                .find(|request| request.url.path() == "/chunk/200")
+                .expect("should be redirected");
+            assert_eq!(redirected.headers.contains_key("referer"), referer);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_resolved_urls_are_reported_once_per_origin() {
+        let mock_server = mock_redirect().await;
+        Mock::given(method("POST"))
+            .and(path("/graphql"))
+            .respond_with(
+                ResponseTemplate::new(307)
+                    .insert_header("location", format!("{}/graphql/v2", mock_server.uri())),
+            )
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/graphql/v2"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "data": {
+                    "transactions": { "pageInfo": { "hasNextPage": false }, "edges": [] },
+                },
+            })))
+            .mount(&mock_server)
+            .await;
+
+        for quiet in [false, true] {
+            let client = Client::builder(&mock_server.uri())
+                .quiet(quiet)
+                .build()
+                .expect("should not fail");
+            let reported = || {
+                client
+                    .reported_redirects
+                    .lock()
+                    .expect("should not be poisoned")
+                    .len()
+            };
+            client
+                .query_transactions(&TransactionQuery::default())
+                .await
+                .expect("should follow");
+            assert_eq!(reported(), if quiet { 0 } else { 1 });
+            // redirected to the same origin as above
+            for _ in 0..3 {
+                client.fetch_chunk_data(100).await.expect("should follow");
+            }
+            assert_eq!(reported(), if quiet { 0 } else { 1 });
+        }
+    }
+
+    #[tokio::test]
+    async fn test_builder_without_following_redirects() {
+        let mock_server = mock_redirect().await;
+        let client = Client::builder(&mock_server.uri())
+            .follow_redirects(false)
+            .build()
+            .expect("should not fail");
+        let err = client.fetch_chunk_data(100).await.expect_err("should fail");
+
+        match err.downcast_ref::<Error>() {
+            Some(Error::Http { status, .. }) => assert_eq!(*status, 302),
+            _ => panic!("unexpected error: {err}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_builder_timeout() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/chunk/100"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(serde_json::json!({ "chunk": "" }))
+                    .set_delay(Duration::from_secs(5)),
+            )
+            .mount(&mock_server)
+            .await;
+
+        let client = Client::builder(&mock_server.uri())
+            .timeout(Duration::from_millis(100))
+            .build()
+            .expect("should not fail");
+        let err = client
+            .fetch_chunk_data(100)
+            .await
+            .expect_err("should time out");
+        assert_eq!(ErrorKind::of(&err), ErrorKind::Network);
+    }
+
+    #[tokio::test]
+    async fn test_fetch_bundle_item_by_range() {
+        let id = "uYpAeGCj8Xe_J0sKiZ_aJ4Zl1zQLgDH5ia-pqtNLJEA";
+        let hex_str = include_str!("../res/uYpAeGCj8Xe_J0sKiZ_aJ4Zl1zQLgDH5ia-pqtNLJEA_data.hex");
+        let data = hex::decode(hex_str).expect("should parse");
+        let expected = bundle::ans104_bundle_data_item_stream(data.as_slice())
+            .try_collect::<Vec<_>>()
+            .await
+            .expect("should parse");
+
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path(format!("/tx/{id}/offset")))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "size": data.len().to_string(),
+                "offset": (1000 + data.len() - 1).to_string(),
+            })))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/chunk/1000"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "chunk": Base64(data.clone()).to_string(),
+            })))
+            .mount(&mock_server)
+            .await;
+        let served = data.clone();
+        Mock::given(method("GET"))
+            .and(path(format!("/{id}")))
+            .respond_with(move |request: &wiremock::Request| {
+                let range = request.headers["range"]
+                    .to_str()
+                    .expect("should be string")
+                    .trim_start_matches("bytes=");
+                let (start, end) = range.split_once('-').expect("should be range");
+                let (start, end): (usize, usize) = (
+                    start.parse().expect("should parse"),
+                    end.parse().expect("should parse"),
+                );
+                ResponseTemplate::new(206).set_body_bytes(served[start..=end].to_vec())
+            })
+            .mount(&mock_server)
+            .await;
+
+        let client = Client::new(&mock_server.uri()).expect("should not fail");
+        let id = Base64::from_str(id).expect("should parse");
+        let data_item = client
+            .fetch_bundle_item(&id, 2)
+            .await
+            .expect("should fetch");
+        assert_eq!(data_item.bundle_id, expected[2].bundle_id);
+        assert_eq!(data_item.data, expected[2].data);
+
+        client
+            .fetch_bundle_item(&id, 4)
+            .await
+            .expect_err("should be out of range");
+    }
+
+    #[tokio::test]
+    async fn test_filter_by_block_height() {
+        let ids: Vec<_> = [
+            "uYpAeGCj8Xe_J0sKiZ_aJ4Zl1zQLgDH5ia-pqtNLJEA",
+            "1HjFxJbql1XVFsLT3ey1-DivwXX6x08lusbEJ5wDgAk",
+            "tU6SVuXCfGSSL8xqFX7JKkWj0lciJHAHqAQY89NGM5k",
+        ]
+        .into_iter()
+        .map(|id| Base64::from_str(id).expect("should parse"))
+        .collect();
+
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/graphql"))
+            .and(body_partial_json(serde_json::json!({
+                "variables": { "block": { "min": 1000 } },
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "data": { "transactions": {
+                    "pageInfo": { "hasNextPage": false },
+                    "edges": [
+                        { "cursor": "a", "node": { "id": ids[2].to_string() } },
+                        { "cursor": "b", "node": { "id": ids[0].to_string() } },
+                    ],
+                }},
+            })))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+        let client = Client::new(&mock_server.uri()).expect("should not fail");
+        let block = BlockFilter {
+            min: Some(1000),
+            max: None,
+        };
+        let mined = client
+            .filter_by_block_height(&ids, block)
+            .await
+            .expect("should work");
+        assert_eq!(mined, [ids[0].clone(), ids[2].clone()]);
+    }
+
+    #[tokio::test]
+    async fn test_peek_item_count() {
+        let id =
+            Base64::from_str("uYpAeGCj8Xe_J0sKiZ_aJ4Zl1zQLgDH5ia-pqtNLJEA").expect("should parse");
+        let hex_str = include_str!("../res/uYpAeGCj8Xe_J0sKiZ_aJ4Zl1zQLgDH5ia-pqtNLJEA_data.hex");
+        let data = hex::decode(hex_str).expect("should parse");
+
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path(format!("/{id}")))
+            .and(header("range", "bytes=0-31"))
+            .respond_with(ResponseTemplate::new(206).set_body_bytes(data[..32].to_vec()))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+        let client = Client::new(&mock_server.uri()).expect("should not fail");
+        assert_eq!(client.peek_item_count(&id).await.expect("should work"), 4);
+
+        // gateway ignoring range, item count comes from the first chunk
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path(format!("/{id}")))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes(data.clone()))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path(format!("/tx/{id}/offset")))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "size": data.len().to_string(),
+                "offset": (1000 + data.len() - 1).to_string(),
+            })))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/chunk/1000"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "chunk": Base64(data).to_string(),
+            })))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+        let client = Client::new(&mock_server.uri()).expect("should not fail");
+        assert_eq!(client.peek_item_count(&id).await.expect("should work"), 4);
+    }
+
+    const VERIFIED_BUNDLE_ID: &str = "uYpAeGCj8Xe_J0sKiZ_aJ4Zl1zQLgDH5ia-pqtNLJEA";
+
+    // serves bundle of single DataItem spanning 3 chunks, optionally with one byte of given chunk flipped
+    async fn mock_bundle_with_proofs(tampered_chunk: Option<usize>) -> MockServer {
+        let data_size = 600_000;
+        let item_size = data_size - 96;
+        let mut data = 1u128.to_le_bytes().to_vec();
+        data.extend([0; 16]);
+        data.extend((item_size as u128).to_le_bytes());
+        data.extend([0; 16]);
+        data.extend([7; 32]);
+        data.extend((0..item_size).map(|i| (i % 251) as u8));
+        let (data_root, chunks) = crate::merkle::test::chunk_data(&data);
+
+        let mock_server = MockServer::start().await;
+        let tx = serde_json::json!({
+            "format": 2,
+            "id": VERIFIED_BUNDLE_ID,
+            "last_tx": "",
+            "owner": "",
+            "tags": [],
+            "target": "",
+            "quantity": "0",
+            "data_root": Base64(data_root).to_string(),
+            "data": "",
+            "data_size": data_size.to_string(),
+            "reward": "0",
+            "signature": "",
+        });
+        Mock::given(method("GET"))
+            .and(path(format!("/tx/{VERIFIED_BUNDLE_ID}")))
+            .respond_with(ResponseTemplate::new(200).set_body_json(tx))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path(format!("/tx/{VERIFIED_BUNDLE_ID}/offset")))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "size": data_size.to_string(),
+                "offset": (1000 + data_size - 1).to_string(),
+            })))
+            .mount(&mock_server)
+            .await;
+
+        let mut chunk_offset = 1000;
+        for (idx, (mut chunk, data_path)) in chunks.into_iter().enumerate() {
+            let chunk_len = chunk.len();
+            if tampered_chunk == Some(idx) {
+                chunk[10] ^= 1;
+            }
+            Mock::given(method("GET"))
+                .and(path(format!("/chunk/{chunk_offset}")))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                    "chunk": Base64(chunk).to_string(),
+                    "data_path": Base64(data_path).to_string(),
+                })))
+                .mount(&mock_server)
+                .await;
+            chunk_offset += chunk_len;
+        }
+        mock_server
+    }
+
+    #[tokio::test]
+    async fn test_verify_bundle() {
+        let id = Base64::from_str(VERIFIED_BUNDLE_ID).expect("should parse");
+
+        let mock_server = mock_bundle_with_proofs(None).await;
+        let client = Client::new(&mock_server.uri()).expect("should not fail");
+        assert!(client.verify_bundle(&id).await.expect("should not fail"));
+
+        let mock_server = mock_bundle_with_proofs(Some(2)).await;
+        let client = Client::new(&mock_server.uri()).expect("should not fail");
+        assert!(!client.verify_bundle(&id).await.expect("should not fail"));
+    }
+
+    #[tokio::test]
+    async fn test_gzip_encoded_chunk_is_decoded() {
+        let chunk = serde_json::json!({ "chunk": Base64(b"chunk data".to_vec()).to_string() });
+        let mut encoder = flate2::write::GzEncoder::new(vec![], flate2::Compression::default());
+        encoder
+            .write_all(chunk.to_string().as_bytes())
+            .expect("should not fail");
+        let body = encoder.finish().expect("should not fail");
+
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/chunk/100"))
+            .and(header("accept-encoding", "gzip"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("content-encoding", "gzip")
+                    .insert_header("content-type", "application/json")
+                    .set_body_bytes(body),
+            )
+            .mount(&mock_server)
+            .await;
+
+        let client = Client::new(&mock_server.uri()).expect("should not fail");
+        let chunk = client.fetch_chunk_data(100).await.expect("should work");
+        assert_eq!(chunk.chunk.0, b"chunk data");
+    }
+
+    #[tokio::test]
+    async fn test_sniffed_gzip_chunk_is_decoded() {
+        let chunk = serde_json::json!({ "chunk": Base64(b"chunk data".to_vec()).to_string() });
+        let mut encoder = flate2::write::GzEncoder::new(vec![], flate2::Compression::default());
+        encoder
+            .write_all(chunk.to_string().as_bytes())
+            .expect("should not fail");
+        let body = encoder.finish().expect("should not fail");
+
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/chunk/100"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("content-type", "application/json")
+                    .set_body_bytes(body),
+            )
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/chunk/200"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(chunk))
+            .mount(&mock_server)
+            .await;
+
+        let client = Client::new(&mock_server.uri()).expect("should not fail");
+        client
+            .fetch_chunk_data(100)
+            .await
+            .expect_err("should fail without sniffing");
+
+        let client = Client::builder(&mock_server.uri())
+            .sniff_compression(true)
+            .build()
+            .expect("should not fail");
+        for offset in [100, 200] {
+            let chunk = client.fetch_chunk_data(offset).await.expect("should work");
+            assert_eq!(chunk.chunk.0, b"chunk data");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_chunk_stream_with_known_offset() {
+        let mock_server = MockServer::start().await;
+        for (offset, chunk) in [(100, b"first "), (106, b"second")] {
+            Mock::given(method("GET"))
+                .and(path(format!("/chunk/{offset}")))
+                .respond_with(ResponseTemplate::new(200).set_body_json(
+                    serde_json::json!({ "chunk": Base64(chunk.to_vec()).to_string() }),
+                ))
+                .expect(1)
+                .mount(&mock_server)
+                .await;
+        }
+
+        let client = Client::new(&mock_server.uri()).expect("should not fail");
+        let chunks = client
+            .transaction_data_chunk_stream_with_offset(111, 12)
+            .try_collect::<Vec<_>>()
+            .await
+            .expect("should work");
+        assert_eq!(chunks.concat(), b"first second");
+        // offset is not fetched
+        assert_eq!(
+            mock_server
+                .received_requests()
+                .await
+                .expect("should be recorded")
+                .len(),
+            2
+        );
+    }
+
+    async fn mock_partially_seeded(missing_responses: u64) -> MockServer {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/chunk/100"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(
+                serde_json::json!({ "chunk": Base64(b"first ".to_vec()).to_string() }),
+            ))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/chunk/106"))
+            .respond_with(ResponseTemplate::new(404))
+            .up_to_n_times(missing_responses)
+            .with_priority(1)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/chunk/106"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(
+                serde_json::json!({ "chunk": Base64(b"second".to_vec()).to_string() }),
+            ))
+            .mount(&mock_server)
+            .await;
+        mock_server
+    }
+
+    #[tokio::test]
+    async fn test_resumed_chunk_stream() {
+        let id = Base64(vec![5; 32]);
+        let mock_server = mock_partially_seeded(1).await;
+        Mock::given(method("GET"))
+            .and(path(format!("/tx/{id}/offset")))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "size": "12",
+                "offset": "111",
+            })))
+            .mount(&mock_server)
+            .await;
+        let client = Client::new(&mock_server.uri()).expect("should not fail");
+        let state_file = std::env::temp_dir().join(format!(
+            "arweave-dumper-chunk-state-{}.json",
+            std::process::id()
+        ));
+        let state = |resume| ChunkStateOptions {
+            state_file: state_file.clone(),
+            resume,
+        };
+
+        client
+            .transaction_data_chunk_stream_with_state(&id, DEFAULT_CHUNK_BUFFER, state(false))
+            .try_collect::<Vec<_>>()
+            .await
+            .expect_err("second chunk should be missing");
+        assert!(state_file.exists());
+
+        let chunks = client
+            .transaction_data_chunk_stream_with_state(&id, DEFAULT_CHUNK_BUFFER, state(true))
+            .try_collect::<Vec<_>>()
+            .await
+            .expect("should work");
+        assert_eq!(chunks.concat(), b"first second");
+        assert!(!state_file.exists(), "should be removed when done");
+
+        // first chunk is not fetched again
+        let first_chunk_requests = mock_server
+            .received_requests()
+            .await
+            .expect("should be recorded")
+            .into_iter()
+            .filter(|request| request.url.path() == "/chunk/100")
+            .count();
+        assert_eq!(first_chunk_requests, 1);
+    }
+
+    #[tokio::test]
+    async fn test_not_fully_seeded_chunk_stream() {
+        let mock_server = mock_partially_seeded(u64::MAX).await;
+        let client = Client::new(&mock_server.uri()).expect("should not fail");
+
+        let err = client
+            .transaction_data_chunk_stream_with_offset(111, 12)
+            .try_collect::<Vec<_>>()
+            .await
+            .expect_err("should fail");
+        match err.downcast_ref::<Error>() {
+            Some(Error::BundleNotFullySeeded { retrieved, size }) => {
+                assert_eq!((*retrieved, *size), (6, 12));
+            }
+            _ => panic!("unexpected error: {err}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_seed_retries() {
+        let mock_server = mock_partially_seeded(2).await;
+        let client = Client::builder(&mock_server.uri())
+            .seed_retries(2)
+            .seed_retry_delay(Duration::from_millis(1))
+            .build()
+            .expect("should not fail");
+
+        let chunks = client
+            .transaction_data_chunk_stream_with_offset(111, 12)
+            .try_collect::<Vec<_>>()
+            .await
+            .expect("should be seeded on last retry");
+        assert_eq!(chunks.concat(), b"first second");
+    }
+
+    #[tokio::test]
+    async fn test_retry_budget_is_shared() {
+        let mock_server = mock_partially_seeded(u64::MAX).await;
+        let client = Client::builder(&mock_server.uri())
+            .seed_retries(2)
+            .seed_retry_delay(Duration::from_millis(1))
+            .max_total_retries(3)
+            .build()
+            .expect("should not fail");
+
+        let err = client
+            .transaction_data_chunk_stream_with_offset(111, 12)
+            .try_collect::<Vec<_>>()
+            .await
+            .expect_err("should fail");
+        assert!(matches!(
+            err.downcast_ref::<Error>(),
+            Some(Error::BundleNotFullySeeded { .. })
+        ));
+        // clone has only one retry left
+        client
+            .clone()
+            .transaction_data_chunk_stream_with_offset(111, 12)
+            .try_collect::<Vec<_>>()
+            .await
+            .expect_err("should fail");
+        let requests = mock_server
+            .received_requests()
+            .await
+            .expect("should be recorded");
+        // both streams fetch both chunks, second one is retried 2 and 1 times
+        assert_eq!(requests.len(), 2 * 2 + 2 + 1);
+    }
+
+    async fn mock_pending_chunk(pending_responses: u64) -> MockServer {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/chunk/100"))
+            .respond_with(ResponseTemplate::new(202))
+            .up_to_n_times(pending_responses)
+            .with_priority(1)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/chunk/100"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(
+                serde_json::json!({ "chunk": Base64(b"chunk".to_vec()).to_string() }),
+            ))
+            .mount(&mock_server)
+            .await;
+        mock_server
+    }
+
+    #[tokio::test]
+    async fn test_pending_chunk_is_retried() {
+        let mock_server = mock_pending_chunk(2).await;
+        let client = Client::builder(&mock_server.uri())
+            .seed_retry_delay(Duration::from_millis(1))
+            .build()
+            .expect("should not fail");
+
+        let chunks = client
+            .transaction_data_chunk_stream_with_offset(104, 5)
+            .try_collect::<Vec<_>>()
+            .await
+            .expect("should be available on retry");
+        assert_eq!(chunks.concat(), b"chunk");
+    }
+
+    #[tokio::test]
+    async fn test_pending_chunk_times_out() {
+        let mock_server = mock_pending_chunk(u64::MAX).await;
+        let client = Client::builder(&mock_server.uri())
+            .seed_retry_delay(Duration::from_millis(1))
+            .pending_chunk_timeout(Duration::from_millis(20))
+            .build()
+            .expect("should not fail");
+
+        let err = client
+            .transaction_data_chunk_stream_with_offset(104, 5)
+            .try_collect::<Vec<_>>()
+            .await
+            .expect_err("should fail");
+        match err.downcast_ref::<Error>() {
+            Some(Error::ChunkPending { offset }) => assert_eq!(*offset, 100),
+            _ => panic!("unexpected error: {err}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_chunk_stream_passes_errors() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/chunk/100"))
+            .respond_with(ResponseTemplate::new(500))
+            .mount(&mock_server)
+            .await;
+        let client = Client::new(&mock_server.uri()).expect("should not fail");
+
+        let err = client
+            .transaction_data_chunk_stream_with_offset(111, 12)
+            .try_collect::<Vec<_>>()
+            .await
+            .expect_err("should fail");
+        assert_eq!(ErrorKind::of(&err), ErrorKind::Http);
+    }
+
+    #[test]
+    fn test_tag_map_keeps_first_value() {
+        let tx = TxMetadata {
+            tags: [
+                ("Bundle-Format", "binary"),
+                ("App-Name", "a"),
+                ("App-Name", "b"),
+            ]
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .to_vec(),
+        };
+        assert_eq!(
+            tx.tag_map(),
+            BTreeMap::from([("App-Name", "a"), ("Bundle-Format", "binary")])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_html_transaction_metadata_is_rejected() {
+        let id = "uYpAeGCj8Xe_J0sKiZ_aJ4Zl1zQLgDH5ia-pqtNLJEA";
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path(format!("/tx/{id}")))
+            .and(header("accept", "application/json"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_raw("<html>Not here</html>", "text/html; charset=utf-8"),
+            )
+            .mount(&mock_server)
+            .await;
+
+        let client = Client::new(&mock_server.uri()).expect("should not fail");
+        let err = client
+            .fetch_transaction(&Base64::from_str(id).expect("should parse"))
+            .await
+            .expect_err("should fail");
+        match err.downcast_ref::<Error>() {
+            Some(Error::UnexpectedContentType { content_type, .. }) => {
+                assert_eq!(content_type, "text/html; charset=utf-8");
+            }
+            _ => panic!("unexpected error: {err}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_fetch_data_item() {
+        let data = hex::decode(include_str!("../res/first_item.hex")).expect("should parse");
+        let expected = bundle::read_data_item(data.as_slice())
+            .await
+            .expect("should parse");
+
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path(format!("/node/tx/{}/raw", expected.bundle_id)))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes(data))
+            .mount(&mock_server)
+            .await;
+
+        let client = Client::builder("http://localhost")
+            .data_item_url(&format!("{}/node/tx/{{id}}/raw", mock_server.uri()))
+            .build()
+            .expect("should not fail");
+        let data_item = client
+            .fetch_data_item(&expected.bundle_id)
+            .await
+            .expect("should fetch");
+        assert_eq!(data_item.data, expected.data);
+
+        let other_id = Base64(vec![0; 32]);
+        client
+            .fetch_data_item(&other_id)
+            .await
+            .expect_err("should fail for unknown DataItem");
+    }
+
+    #[tokio::test]
+    async fn test_metadata_cache() {
+        let id = Base64(vec![9; 32]);
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path(format!("/tx/{id}/offset")))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "size": "10",
+                "offset": "109",
+            })))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+        // pending transaction is asked for again
+        Mock::given(method("GET"))
+            .and(path(format!("/tx/{id}")))
+            .respond_with(ResponseTemplate::new(202))
+            .up_to_n_times(1)
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path(format!("/tx/{id}")))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "format": 2,
+                "id": id.to_string(),
+                "last_tx": "",
+                "owner": "",
+                "tags": [],
+                "target": "",
+                "quantity": "0",
+                "data_root": "",
+                "data": "",
+                "data_size": "10",
+                "reward": "0",
+                "signature": "",
+            })))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let client = Client::new(&mock_server.uri())
+            .expect("should not fail")
+            .with_metadata_cache(10);
+        for _ in 0..3 {
+            let offset = client
+                .clone()
+                .fetch_transaction_offset(&id)
+                .await
+                .expect("should fetch");
+            assert_eq!(offset.start_offset().expect("should be valid"), 100);
+        }
+        let err = client
+            .fetch_transaction(&id)
+            .await
+            .expect_err("should be pending");
+        assert!(matches!(err.downcast_ref(), Some(Error::Pending)));
+        for _ in 0..2 {
+            let tx = client.fetch_transaction(&id).await.expect("should fetch");
+            assert!(!tx.is_bundle());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_fetch_price() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/price/1024"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("5912345\n"))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/price/1"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("<html>"))
+            .mount(&mock_server)
+            .await;
+
+        let client = Client::new(&mock_server.uri()).expect("should not fail");
+        assert_eq!(
+            client.fetch_price(1024).await.expect("should fetch"),
+            5_912_345
+        );
+        client.fetch_price(1).await.expect_err("should fail");
+    }
+
+    #[test]
+    fn test_data_item_url_needs_placeholder() {
+        Client::builder("http://localhost")
+            .data_item_url("http://localhost/tx/raw")
+            .build()
+            .err()
+            .expect("should fail");
+    }
 }
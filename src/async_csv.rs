@@ -0,0 +1,185 @@
+use async_trait::async_trait;
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+
+use crate::{bundle::DataItem, sink::OutputSink};
+
+// number of leading owner public key characters kept in CSV
+const OWNER_PREFIX_LEN: usize = 12;
+
+/// Writes one CSV row of metadata per DataItem, optionally with value of chosen tag as last column.
+pub struct CsvWriter<W> {
+    tag: Option<String>,
+    index: usize,
+    writer: W,
+}
+
+impl<W> CsvWriter<W> {
+    pub fn new(writer: W, tag: Option<String>) -> Self {
+        Self {
+            tag,
+            index: 0,
+            writer,
+        }
+    }
+
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.writer
+    }
+}
+
+impl<W> CsvWriter<W>
+where
+    W: AsyncWrite + Unpin,
+{
+    pub async fn write_header(&mut self) -> anyhow::Result<()> {
+        let mut header = vec![
+            "index",
+            "bundle_id",
+            "signature_name",
+            "owner",
+            "target",
+            "anchor",
+            "tag_count",
+            "data_len",
+        ];
+        let tag = self.tag.clone();
+        if let Some(tag) = &tag {
+            header.push(tag);
+        }
+        self.write_record(&header).await
+    }
+
+    pub async fn write_item(&mut self, data_item: &DataItem) -> anyhow::Result<()> {
+        let mut owner = data_item.owner_public_key.to_string();
+        if owner.len() > OWNER_PREFIX_LEN {
+            owner.truncate(OWNER_PREFIX_LEN);
+            owner.push_str("...");
+        }
+        let mut record = vec![
+            self.index.to_string(),
+            data_item.bundle_id.to_string(),
+            data_item.signature_name.clone(),
+            owner,
+            data_item
+                .target
+                .as_ref()
+                .map(ToString::to_string)
+                .unwrap_or_default(),
+            data_item
+                .anchor
+                .as_ref()
+                .map(ToString::to_string)
+                .unwrap_or_default(),
+            data_item.tags.len().to_string(),
+            data_item.data_len().to_string(),
+        ];
+        if let Some(tag) = &self.tag {
+            record.push(data_item.get_tag(tag).unwrap_or_default().to_string());
+        }
+        self.write_record(&record).await?;
+        self.index += 1;
+        Ok(())
+    }
+
+    // csv crate writes synchronously only, so every record is encoded into buffer first
+    async fn write_record<T: AsRef<[u8]>>(&mut self, record: &[T]) -> anyhow::Result<()> {
+        let mut csv_writer = csv::WriterBuilder::new().from_writer(Vec::with_capacity(256));
+        csv_writer.write_record(record)?;
+        let buffer = csv_writer.into_inner()?;
+        self.writer.write_all(&buffer).await?;
+        Ok(())
+    }
+}
+
+#[async_trait(?Send)]
+impl<W> OutputSink for CsvWriter<W>
+where
+    W: AsyncWrite + Unpin,
+{
+    async fn open(&mut self) -> anyhow::Result<()> {
+        self.write_header().await
+    }
+
+    async fn write_item(&mut self, data_item: &DataItem) -> anyhow::Result<()> {
+        CsvWriter::write_item(self, data_item).await
+    }
+
+    async fn flush(&mut self) -> anyhow::Result<()> {
+        self.writer.flush().await?;
+        Ok(())
+    }
+
+    async fn finish(&mut self) -> anyhow::Result<()> {
+        OutputSink::flush(self).await
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use arweave_rs::crypto::base64::Base64;
+
+    use super::*;
+    use crate::avro::BundleTag;
+
+    fn data_item(tags: Vec<BundleTag>) -> DataItem {
+        DataItem {
+            signature_name: "arweave".to_string(),
+            signature_type: 1,
+            signature: Base64(vec![1; 4]),
+            bundle_id: Base64(vec![2; 3]),
+            owner_public_key: Base64(vec![3; 32]),
+            target: None,
+            anchor: Some(Base64(vec![4; 3])),
+            tags,
+            data: Base64(vec![5; 10]),
+            data_file: None,
+            data_sha256: None,
+            raw_tags: vec![],
+            position: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_csv_writer() {
+        let mut writer = Vec::new();
+        let mut csv_writer = CsvWriter::new(&mut writer, Some("Content-Type".to_string()));
+
+        csv_writer.write_header().await.expect("should not fail");
+        csv_writer
+            .write_item(&data_item(vec![BundleTag {
+                name: "Content-Type".to_string(),
+                value: "text/plain, utf-8".to_string(),
+            }]))
+            .await
+            .expect("should not fail");
+        csv_writer
+            .write_item(&data_item(vec![]))
+            .await
+            .expect("should not fail");
+
+        assert_eq!(
+            String::from_utf8(writer).expect("should be utf8"),
+            "index,bundle_id,signature_name,owner,target,anchor,tag_count,data_len,Content-Type\n\
+             0,AgIC,arweave,AwMDAwMDAwMD...,,BAQE,1,10,\"text/plain, utf-8\"\n\
+             1,AgIC,arweave,AwMDAwMDAwMD...,,BAQE,0,10,\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_csv_writer_without_tag_column() {
+        let mut writer = Vec::new();
+        let mut csv_writer = CsvWriter::new(&mut writer, None);
+
+        csv_writer.write_header().await.expect("should not fail");
+        csv_writer
+            .write_item(&data_item(vec![]))
+            .await
+            .expect("should not fail");
+
+        assert_eq!(
+            String::from_utf8(writer).expect("should be utf8"),
+            "index,bundle_id,signature_name,owner,target,anchor,tag_count,data_len\n\
+             0,AgIC,arweave,AwMDAwMDAwMD...,,BAQE,0,10\n"
+        );
+    }
+}
@@ -1,21 +1,43 @@
+use std::io;
+
 use serde::Serialize;
+use serde_json::ser::{CompactFormatter, Formatter};
 use tokio::io::AsyncWrite;
 use tokio::io::AsyncWriteExt;
 
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum JsonFormat {
+    #[default]
+    Pretty,
+    Compact,
+    /// compact items with their `tags` array broken into lines, one tag per line
+    CompactReadableTags,
+}
+
 pub struct ArrayWriter<W> {
     buffer: Vec<u8>,
     following_item: bool,
+    format: JsonFormat,
     writer: W,
 }
 
 impl<W> ArrayWriter<W> {
     pub fn new(writer: W) -> Self {
+        Self::with_format(writer, JsonFormat::default())
+    }
+
+    pub fn with_format(writer: W, format: JsonFormat) -> Self {
         Self {
             following_item: false,
             buffer: Vec::with_capacity(10 * 1024),
+            format,
             writer,
         }
     }
+
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.writer
+    }
 }
 
 impl<W> ArrayWriter<W>
@@ -43,7 +65,129 @@ where
             self.following_item = true;
         }
 
-        serde_json::to_writer_pretty(&mut self.buffer, item)?;
+        match self.format {
+            JsonFormat::Pretty => serde_json::to_writer_pretty(&mut self.buffer, item)?,
+            JsonFormat::Compact => serde_json::to_writer(&mut self.buffer, item)?,
+            JsonFormat::CompactReadableTags => {
+                let mut serializer = serde_json::Serializer::with_formatter(
+                    &mut self.buffer,
+                    ReadableTagsFormatter::default(),
+                );
+                item.serialize(&mut serializer)?
+            }
+        }
+
+        self.writer.write_all(self.buffer.as_slice()).await?;
+
+        Ok(())
+    }
+}
+
+// compact formatter which puts every element of item level `tags` array on its own line
+#[derive(Default)]
+struct ReadableTagsFormatter {
+    depth: usize,
+    in_key: bool,
+    key: Vec<u8>,
+    in_tags: bool,
+}
+
+impl Formatter for ReadableTagsFormatter {
+    fn begin_object<W: ?Sized + io::Write>(&mut self, writer: &mut W) -> io::Result<()> {
+        self.depth += 1;
+        CompactFormatter.begin_object(writer)
+    }
+
+    fn end_object<W: ?Sized + io::Write>(&mut self, writer: &mut W) -> io::Result<()> {
+        self.depth -= 1;
+        CompactFormatter.end_object(writer)
+    }
+
+    fn begin_object_key<W: ?Sized + io::Write>(
+        &mut self,
+        writer: &mut W,
+        first: bool,
+    ) -> io::Result<()> {
+        self.in_key = true;
+        self.key.clear();
+        CompactFormatter.begin_object_key(writer, first)
+    }
+
+    fn end_object_key<W: ?Sized + io::Write>(&mut self, writer: &mut W) -> io::Result<()> {
+        self.in_key = false;
+        CompactFormatter.end_object_key(writer)
+    }
+
+    fn write_string_fragment<W: ?Sized + io::Write>(
+        &mut self,
+        writer: &mut W,
+        fragment: &str,
+    ) -> io::Result<()> {
+        if self.in_key {
+            self.key.extend_from_slice(fragment.as_bytes());
+        }
+        CompactFormatter.write_string_fragment(writer, fragment)
+    }
+
+    fn begin_array<W: ?Sized + io::Write>(&mut self, writer: &mut W) -> io::Result<()> {
+        self.depth += 1;
+        // array directly in the item object under the `tags` key
+        self.in_tags = self.depth == 2 && self.key == b"tags";
+        CompactFormatter.begin_array(writer)
+    }
+
+    fn begin_array_value<W: ?Sized + io::Write>(
+        &mut self,
+        writer: &mut W,
+        first: bool,
+    ) -> io::Result<()> {
+        CompactFormatter.begin_array_value(writer, first)?;
+        if self.in_tags && self.depth == 2 {
+            writer.write_all(b"\n  ")?;
+        }
+        Ok(())
+    }
+
+    fn end_array<W: ?Sized + io::Write>(&mut self, writer: &mut W) -> io::Result<()> {
+        if self.in_tags && self.depth == 2 {
+            self.in_tags = false;
+            writer.write_all(b"\n")?;
+        }
+        self.depth -= 1;
+        CompactFormatter.end_array(writer)
+    }
+}
+
+/// Writes items as newline delimited JSON, one compact JSON object per line.
+pub struct NdJsonWriter<W> {
+    buffer: Vec<u8>,
+    writer: W,
+}
+
+impl<W> NdJsonWriter<W> {
+    pub fn new(writer: W) -> Self {
+        Self {
+            buffer: Vec::with_capacity(1024),
+            writer,
+        }
+    }
+
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.writer
+    }
+}
+
+impl<W> NdJsonWriter<W>
+where
+    W: AsyncWrite + Unpin,
+{
+    pub async fn write_item<I>(&mut self, item: &I) -> anyhow::Result<()>
+    where
+        I: Serialize + ?Sized,
+    {
+        self.buffer.clear();
+        serde_json::to_writer(&mut self.buffer, item)?;
+        self.buffer.push(b'\n');
 
         self.writer.write_all(self.buffer.as_slice()).await?;
 
@@ -88,4 +232,70 @@ mod test {
             "[\n\"abc\",\n\"123\",\n\"last\"\n]\n"
         )
     }
+
+    #[tokio::test]
+    async fn test_compact_array_writer() {
+        let mut writer = Vec::with_capacity(100);
+        let mut array_writer = ArrayWriter::with_format(&mut writer, JsonFormat::Compact);
+
+        array_writer
+            .write_open_bracket()
+            .await
+            .expect("should not fail");
+        array_writer
+            .write_item(&serde_json::json!({ "a": [1, 2] }))
+            .await
+            .expect("should not fail");
+        array_writer
+            .write_close_bracket()
+            .await
+            .expect("should not fail");
+
+        assert_eq!(String::from_utf8_lossy(&writer), "[\n{\"a\":[1,2]}\n]\n")
+    }
+
+    #[tokio::test]
+    async fn test_readable_tags_array_writer() {
+        let mut writer = Vec::with_capacity(100);
+        let mut array_writer =
+            ArrayWriter::with_format(&mut writer, JsonFormat::CompactReadableTags);
+
+        let item = serde_json::json!({
+            "id": "abc",
+            "tags": [{ "name": "a", "value": "1" }, { "name": "b", "value": "2" }],
+            "other": { "tags": [1, 2] },
+        });
+        array_writer
+            .write_item(&item)
+            .await
+            .expect("should not fail");
+
+        let output = String::from_utf8_lossy(&writer);
+        assert_eq!(
+            output,
+            "{\"id\":\"abc\",\"other\":{\"tags\":[1,2]},\"tags\":[\n  {\"name\":\"a\",\"value\":\"1\"},\n  {\"name\":\"b\",\"value\":\"2\"}\n]}"
+        );
+        let parsed: serde_json::Value = serde_json::from_str(&output).expect("should parse");
+        assert_eq!(parsed, item);
+    }
+
+    #[tokio::test]
+    async fn test_ndjson_writer() {
+        let mut writer = Vec::with_capacity(100);
+        let mut ndjson_writer = NdJsonWriter::new(&mut writer);
+
+        ndjson_writer
+            .write_item(&serde_json::json!({ "a": [1, 2] }))
+            .await
+            .expect("should not fail");
+        ndjson_writer
+            .write_item("last")
+            .await
+            .expect("should not fail");
+
+        assert_eq!(
+            String::from_utf8_lossy(&writer),
+            "{\"a\":[1,2]}\n\"last\"\n"
+        )
+    }
 }
@@ -0,0 +1,178 @@
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+const BLOCK_SIZE: usize = 512;
+// path has to fit into name field, prefix field is not used
+const MAX_PATH_LEN: usize = 100;
+
+/// Streams regular file entries of ustar archive into writer, entry data is never buffered as a whole,
+/// see <https://www.gnu.org/software/tar/manual/html_node/Standard.html>
+pub struct TarWriter<W> {
+    mtime: u64,
+    writer: W,
+}
+
+impl<W> TarWriter<W> {
+    /// Entries get current time as their modification time.
+    pub fn new(writer: W) -> Self {
+        let mtime = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_secs())
+            .unwrap_or_default();
+        Self { mtime, writer }
+    }
+
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.writer
+    }
+}
+
+impl<W> TarWriter<W>
+where
+    W: AsyncWrite + Unpin,
+{
+    pub async fn append(&mut self, path: &str, data: &[u8]) -> anyhow::Result<()> {
+        self.append_reader(path, data.len() as u64, data).await
+    }
+
+    /// Copies exactly `size` bytes of entry data from reader.
+    pub async fn append_reader<R>(&mut self, path: &str, size: u64, reader: R) -> anyhow::Result<()>
+    where
+        R: AsyncRead + Unpin,
+    {
+        self.writer
+            .write_all(&header(path, size, self.mtime)?)
+            .await?;
+        let copied = tokio::io::copy(&mut reader.take(size), &mut self.writer).await?;
+        if copied != size {
+            return Err(anyhow::anyhow!(
+                "tar entry {path} should have {size} bytes, but only {copied} were read"
+            ));
+        }
+        let padding = (BLOCK_SIZE - size as usize % BLOCK_SIZE) % BLOCK_SIZE;
+        self.writer.write_all(&[0; BLOCK_SIZE][..padding]).await?;
+        Ok(())
+    }
+
+    /// Writes end of archive marker and flushes.
+    pub async fn finish(&mut self) -> anyhow::Result<()> {
+        self.writer.write_all(&[0; 2 * BLOCK_SIZE]).await?;
+        self.writer.flush().await?;
+        Ok(())
+    }
+}
+
+fn header(path: &str, size: u64, mtime: u64) -> anyhow::Result<[u8; BLOCK_SIZE]> {
+    if path.len() > MAX_PATH_LEN {
+        return Err(anyhow::anyhow!(
+            "tar entry path {path} is longer than {MAX_PATH_LEN} bytes"
+        ));
+    }
+    let mut header = [0; BLOCK_SIZE];
+    header[..path.len()].copy_from_slice(path.as_bytes());
+    write_octal(&mut header[100..108], 0o644)?; // mode
+    write_octal(&mut header[108..116], 0)?; // uid
+    write_octal(&mut header[116..124], 0)?; // gid
+    write_octal(&mut header[124..136], size)?;
+    write_octal(&mut header[136..148], mtime)?;
+    header[156] = b'0'; // regular file
+    header[257..263].copy_from_slice(b"ustar\0");
+    header[263..265].copy_from_slice(b"00");
+
+    // checksum is computed as if its own field was filled with spaces
+    header[148..156].fill(b' ');
+    let checksum = header.iter().map(|b| *b as u64).sum::<u64>();
+    write_octal(&mut header[148..155], checksum)?;
+    Ok(header)
+}
+
+// zero padded octal number terminated by NUL
+fn write_octal(field: &mut [u8], value: u64) -> anyhow::Result<()> {
+    let width = field.len() - 1;
+    let digits = format!("{value:0width$o}");
+    if digits.len() > width {
+        return Err(anyhow::anyhow!(
+            "value {value} does not fit into {width} octal digits of tar header"
+        ));
+    }
+    field[..width].copy_from_slice(digits.as_bytes());
+    field[width] = 0;
+    Ok(())
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use super::*;
+
+    /// Entry paths and data of the archive.
+    pub(crate) fn read_entries(mut archive: &[u8]) -> Vec<(String, Vec<u8>)> {
+        let mut entries = vec![];
+        while archive[..BLOCK_SIZE].iter().any(|b| *b != 0) {
+            let (header, rest) = archive.split_at(BLOCK_SIZE);
+            let checksum = header
+                .iter()
+                .enumerate()
+                .map(|(idx, b)| match idx {
+                    148..156 => b' ' as u64,
+                    _ => *b as u64,
+                })
+                .sum::<u64>();
+            let octal = |field: &[u8]| {
+                let digits = std::str::from_utf8(field).expect("should be utf8");
+                u64::from_str_radix(digits.trim_end_matches(['\0', ' ']), 8).expect("should parse")
+            };
+            assert_eq!(octal(&header[148..156]), checksum);
+
+            let path = std::str::from_utf8(&header[..MAX_PATH_LEN])
+                .expect("should be utf8")
+                .trim_end_matches('\0')
+                .to_string();
+            let size = octal(&header[124..136]) as usize;
+            entries.push((path, rest[..size].to_vec()));
+            archive = &rest[size.div_ceil(BLOCK_SIZE) * BLOCK_SIZE..];
+        }
+        assert_eq!(archive, [0; 2 * BLOCK_SIZE]);
+        entries
+    }
+
+    #[tokio::test]
+    async fn test_tar_writer() {
+        let mut archive = vec![];
+        let mut tar_writer = TarWriter::new(&mut archive);
+        tar_writer
+            .append("a.txt", b"hello")
+            .await
+            .expect("should not fail");
+        tar_writer
+            .append_reader("b.bin", 512, [7u8; 600].as_slice())
+            .await
+            .expect("should not fail");
+        tar_writer
+            .append("empty", b"")
+            .await
+            .expect("should not fail");
+        tar_writer.finish().await.expect("should not fail");
+
+        assert_eq!(archive.len() % BLOCK_SIZE, 0);
+        assert_eq!(
+            read_entries(&archive),
+            vec![
+                ("a.txt".to_string(), b"hello".to_vec()),
+                ("b.bin".to_string(), vec![7; 512]),
+                ("empty".to_string(), vec![]),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_tar_writer_rejects_short_reader() {
+        let mut tar_writer = TarWriter::new(vec![]);
+        tar_writer
+            .append_reader("short", 10, [1u8; 5].as_slice())
+            .await
+            .expect_err("should fail");
+        tar_writer
+            .append(&"x".repeat(101), b"")
+            .await
+            .expect_err("should fail");
+    }
+}
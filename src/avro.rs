@@ -1,5 +1,8 @@
+#[cfg(feature = "fast-avro")]
+use std::io::Read;
 use std::sync::LazyLock;
 
+use anyhow::Context;
 use apache_avro::Schema;
 use serde::{Deserialize, Serialize};
 
@@ -26,18 +29,163 @@ const SCHEMA_STR: &str = r#"
 static SCHEMA_INSTANCE: LazyLock<Schema> =
     LazyLock::new(|| apache_avro::Schema::parse_str(SCHEMA_STR).expect("should parse"));
 
-pub fn parse_tag_list<R>(mut reader: R) -> anyhow::Result<Vec<BundleTag>>
+/// ANS-104 tag array schema, used by [`parse_tag_list`].
+pub fn default_tag_schema() -> &'static Schema {
+    &SCHEMA_INSTANCE
+}
+
+pub fn parse_tag_list<R>(reader: R) -> anyhow::Result<Vec<BundleTag>>
 where
     R: std::io::Read,
 {
-    let value =
-        apache_avro::from_avro_datum(&SCHEMA_INSTANCE, &mut reader, Some(&SCHEMA_INSTANCE))?;
+    #[cfg(feature = "fast-avro")]
+    return parse_tag_list_fast(reader);
+    #[cfg(not(feature = "fast-avro"))]
+    return parse_tag_list_generic(reader);
+}
+
+/// Decodes tags like [`parse_tag_list`] and checks that there are `expected_count` of them,
+/// as declared by DataItem header. Empty bytes are empty tag list, which is how zero tags size encodes it.
+pub fn parse_tag_list_checked(bytes: &[u8], expected_count: u64) -> anyhow::Result<Vec<BundleTag>> {
+    if bytes.is_empty() && expected_count == 0 {
+        return Ok(vec![]);
+    }
+    // the only tag data consistent with zero tag count is empty Avro array
+    if expected_count == 0 && bytes != [0] {
+        return Err(anyhow::anyhow!(
+            "tag count is 0 but tags size is {} bytes of non empty tag data",
+            bytes.len()
+        ));
+    }
+    let tags = parse_tag_list(bytes).context("Avro tags parse")?;
+    if tags.len() as u64 != expected_count {
+        return Err(anyhow::anyhow!(
+            "tag count {expected_count} does not match number of decoded tags {} (tags size: {})",
+            tags.len(),
+            bytes.len()
+        ));
+    }
+    Ok(tags)
+}
+
+/// Encodes tags the way ANS-104 clients do, as single Avro array block with negative count
+/// followed by block size, no tags as no bytes at all.
+/// Recreates raw tags of DataItems read back from JSON, which keeps only decoded ones.
+pub fn encode_tag_list(tags: &[BundleTag]) -> Vec<u8> {
+    let mut bytes = vec![];
+    if tags.is_empty() {
+        return bytes;
+    }
+    let mut block = vec![];
+    for tag in tags {
+        for field in [&tag.name, &tag.value] {
+            write_long(&mut block, field.len() as i64);
+            block.extend_from_slice(field.as_bytes());
+        }
+    }
+    write_long(&mut bytes, -(tags.len() as i64));
+    write_long(&mut bytes, block.len() as i64);
+    bytes.extend(block);
+    write_long(&mut bytes, 0);
+    bytes
+}
+
+// zigzag encoded variable length long
+fn write_long(bytes: &mut Vec<u8>, value: i64) {
+    let mut encoded = ((value << 1) ^ (value >> 63)) as u64;
+    while encoded >= 0x80 {
+        bytes.push(encoded as u8 | 0x80);
+        encoded >>= 7;
+    }
+    bytes.push(encoded as u8);
+}
+
+/// Decodes tags through generic `apache_avro` datum and value conversion.
+pub fn parse_tag_list_generic<R>(reader: R) -> anyhow::Result<Vec<BundleTag>>
+where
+    R: std::io::Read,
+{
+    parse_tag_list_with_schema(reader, default_tag_schema())
+}
+
+/// Decodes tags written with a non-standard tag array schema, which serves as reader schema too.
+/// Tag records must have `name` and `value` string fields, other fields are skipped.
+/// Always goes through generic decoding, also with `fast-avro` feature.
+pub fn parse_tag_list_with_schema<R>(
+    mut reader: R,
+    schema: &Schema,
+) -> anyhow::Result<Vec<BundleTag>>
+where
+    R: std::io::Read,
+{
+    let value = apache_avro::from_avro_datum(schema, &mut reader, Some(schema))?;
     let tags = apache_avro::from_value(&value)?;
     Ok(tags)
 }
 
+/// Decodes tags directly from Avro binary encoding of the tag array schema,
+/// skipping intermediate `apache_avro::types::Value` tree.
+#[cfg(feature = "fast-avro")]
+pub fn parse_tag_list_fast<R>(mut reader: R) -> anyhow::Result<Vec<BundleTag>>
+where
+    R: std::io::Read,
+{
+    let mut tags = vec![];
+    loop {
+        let mut block_count = read_long(&mut reader)?;
+        if block_count == 0 {
+            return Ok(tags);
+        }
+        if block_count < 0 {
+            // negative count is followed by block size in bytes, not needed here
+            block_count = block_count
+                .checked_neg()
+                .ok_or_else(|| anyhow::anyhow!("Invalid Avro block count: {block_count}"))?;
+            read_long(&mut reader)?;
+        }
+        for _ in 0..block_count {
+            let name = read_string(&mut reader)?;
+            let value = read_string(&mut reader)?;
+            tags.push(BundleTag { name, value });
+        }
+    }
+}
+
+// zigzag encoded variable length long
+#[cfg(feature = "fast-avro")]
+fn read_long<R: std::io::Read>(reader: &mut R) -> anyhow::Result<i64> {
+    let mut encoded = 0u64;
+    for i in 0..10 {
+        let mut byte = [0u8; 1];
+        reader.read_exact(&mut byte)?;
+        encoded |= u64::from(byte[0] & 0x7f) << (7 * i);
+        if byte[0] & 0x80 == 0 {
+            return Ok((encoded >> 1) as i64 ^ -((encoded & 1) as i64));
+        }
+    }
+    Err(anyhow::anyhow!("Avro long is longer than 10 bytes"))
+}
+
+#[cfg(feature = "fast-avro")]
+fn read_string<R: std::io::Read>(reader: &mut R) -> anyhow::Result<String> {
+    let len = read_long(reader)?;
+    let len =
+        u64::try_from(len).map_err(|_| anyhow::anyhow!("Negative Avro string length: {len}"))?;
+    let mut buf = vec![];
+    reader.by_ref().take(len).read_to_end(&mut buf)?;
+    if buf.len() as u64 != len {
+        return Err(anyhow::anyhow!(
+            "Avro string truncated: expected {len} bytes, got {}",
+            buf.len()
+        ));
+    }
+    Ok(String::from_utf8(buf)?)
+}
+
 #[cfg(test)]
 mod test {
+    use apache_avro::types::Value;
+
     use super::*;
 
     #[test]
@@ -48,4 +196,111 @@ mod test {
         let tags = parse_tag_list(data.as_slice()).expect("should parse");
         assert_eq!(tags.len(), 18);
     }
+
+    #[test]
+    fn test_checked_tags_read() {
+        let hex_str = include_str!("../res/first_item_tags.hex");
+        let data = hex::decode(hex_str).expect("should parse");
+
+        let tags = parse_tag_list_checked(&data, 18).expect("should parse");
+        assert_eq!(tags.len(), 18);
+        let err = parse_tag_list_checked(&data, 17).expect_err("should fail");
+        assert!(err.to_string().contains("does not match"), "{err:#}");
+        parse_tag_list_checked(&data, 0).expect_err("should fail");
+    }
+
+    #[test]
+    fn test_encoded_tags_match_serialized() {
+        let hex_str = include_str!("../res/first_item_tags.hex");
+        let data = hex::decode(hex_str).expect("should parse");
+
+        let tags = parse_tag_list(data.as_slice()).expect("should parse");
+        assert_eq!(encode_tag_list(&tags), data);
+        assert!(encode_tag_list(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_checked_empty_tags_read() {
+        for data in [&[][..], &[0]] {
+            let tags = parse_tag_list_checked(data, 0).expect("should parse");
+            assert!(tags.is_empty());
+        }
+        parse_tag_list_checked(&[], 1).expect_err("should fail");
+    }
+
+    #[test]
+    fn test_tags_read_with_extended_schema() {
+        let schema = Schema::parse_str(
+            r#"{
+              "type": "array",
+              "items": {
+                "type": "record",
+                "name": "Tag",
+                "fields": [
+                  { "name": "name", "type": "string" },
+                  { "name": "value", "type": "string" },
+                  { "name": "flags", "type": "int" }
+                ]
+              }
+            }"#,
+        )
+        .expect("should parse");
+        let tag = Value::Record(vec![
+            ("name".to_string(), Value::String("App-Name".to_string())),
+            ("value".to_string(), Value::String("private".to_string())),
+            ("flags".to_string(), Value::Int(7)),
+        ]);
+        let data =
+            apache_avro::to_avro_datum(&schema, Value::Array(vec![tag])).expect("should encode");
+
+        let tags = parse_tag_list_with_schema(data.as_slice(), &schema).expect("should parse");
+        assert_eq!(
+            tags,
+            vec![BundleTag {
+                name: "App-Name".to_string(),
+                value: "private".to_string()
+            }]
+        );
+        parse_tag_list_with_schema(data.as_slice(), default_tag_schema())
+            .expect_err("should not fit default schema");
+    }
+
+    #[cfg(feature = "fast-avro")]
+    #[test]
+    fn test_fast_decoder_matches_generic() {
+        let hex_str = include_str!("../res/first_item_tags.hex");
+        let data = hex::decode(hex_str).expect("should parse");
+
+        let fast = parse_tag_list_fast(data.as_slice()).expect("should parse");
+        let generic = parse_tag_list_generic(data.as_slice()).expect("should parse");
+        assert_eq!(fast, generic);
+    }
+
+    #[cfg(feature = "fast-avro")]
+    #[test]
+    fn test_fast_decoder_reads_negative_block_count() {
+        // one block of -1 items with block size 4, then end marker
+        let data = [0x01, 0x08, 0x02, b'a', 0x02, b'b', 0x00];
+
+        let tags = parse_tag_list_fast(data.as_slice()).expect("should parse");
+        assert_eq!(
+            tags,
+            vec![BundleTag {
+                name: "a".to_string(),
+                value: "b".to_string()
+            }]
+        );
+        assert_eq!(
+            parse_tag_list_generic(data.as_slice()).expect("should parse"),
+            tags
+        );
+    }
+
+    #[cfg(feature = "fast-avro")]
+    #[test]
+    fn test_fast_decoder_rejects_truncated_string() {
+        let data = [0x02, 0x0a, b'a'];
+
+        parse_tag_list_fast(data.as_slice()).expect_err("should fail");
+    }
 }
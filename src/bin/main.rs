@@ -1,78 +1,1894 @@
-use arweave_dumper::{arweave, async_json, bundle};
+use std::{
+    collections::{HashSet, VecDeque},
+    io::Cursor,
+    path::PathBuf,
+    process::ExitCode,
+};
+
+use anyhow::Context;
+#[cfg(feature = "csv")]
+use arweave_dumper::async_csv;
+#[cfg(feature = "s3")]
+use arweave_dumper::s3;
+use arweave_dumper::{
+    arweave::{self, TxMetadata},
+    async_json::{self, JsonFormat},
+    budget::MemoryBudget,
+    bundle::{self, ContentTypePattern, DataItem},
+    chunk_state::ChunkStateOptions,
+    dump,
+    error::{self, Error, ErrorReport},
+    graphql::{BlockFilter, SortOrder, TagFilter, TransactionQuery},
+    index, metrics,
+    output::{self, OutputOptions, TagTriple, TagsRecord},
+    signature::SignatureScheme,
+    sink::{JsonArraySink, OutputSink, SplitSink, TarSink},
+    stats::{self, Stats},
+};
 use arweave_rs::crypto::base64::Base64;
-use clap::{command, Parser};
-use futures_util::{pin_mut, TryStreamExt};
-use tokio::io::AsyncWriteExt;
-use tokio_util::io::StreamReader;
+use clap::{builder::RangedU64ValueParser, Parser, Subcommand, ValueEnum};
+use futures_util::{stream::LocalBoxStream, StreamExt, TryStreamExt};
+use tokio::io::{AsyncRead, AsyncWrite, AsyncWriteExt};
+use tokio_util::{bytes::Bytes, io::StreamReader, sync::CancellationToken};
+
+// informational output always goes to stderr, so that stdout carries data only
+macro_rules! info {
+    ($args:expr) => {
+        info!($args, "")
+    };
+    ($args:expr, $($arg:tt)*) => {
+        if !$args.quiet {
+            eprintln!($($arg)*)
+        }
+    };
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum DataSource {
+    /// Assemble transaction data from chunks
+    Chunks,
+    /// Download transaction data as served by the gateway
+    Served,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+enum VerifyMode {
+    /// Fail on the first DataItem with invalid signature
+    FailFast,
+    /// Verify all DataItems, write them all and fail with list of invalid ones at the end
+    Report,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum OutputFormat {
+    /// JSON array of DataItems
+    Json,
+    /// CSV of DataItem metadata, without data itself
+    #[cfg(feature = "csv")]
+    Csv,
+}
+
+impl OutputFormat {
+    fn extension(&self) -> &'static str {
+        match self {
+            Self::Json => "json",
+            #[cfg(feature = "csv")]
+            Self::Csv => "csv",
+        }
+    }
+}
+
+// one data chunk, so that small parser reads do not go through StreamReader one by one
+const DEFAULT_READ_BUFFER: usize = 256 * 1024;
+const DEFAULT_WRITE_BUFFER: usize = 64 * 1024;
+// --follow stops polling at the first seen id, only the most recent ones need to be remembered
+const FOLLOW_SEEN_LIMIT: usize = 10_000;
+// polls on which failed --follow dump is retried before it is given up
+const FOLLOW_RETRIES: usize = 3;
+// --follow errors file keeps only this many most recent given up dumps, it is rewritten on each one
+const FOLLOW_ERRORS_LIMIT: usize = 1_000;
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Fetch single DataItem of the bundle and print its metadata and tags, without data
+    Item {
+        /// Bundle transaction ID
+        #[arg(short, long, value_parser = arweave::parse_transaction_id)]
+        transaction_id: Base64,
+
+        /// Position of the DataItem in the bundle, starting from 0
+        #[arg(long)]
+        index: usize,
+    },
+    /// Fetch single DataItem straight from bundling service and print its metadata and tags, without data
+    DataItem {
+        /// DataItem ID
+        #[arg(long, value_parser = arweave::parse_transaction_id)]
+        id: Base64,
+
+        /// DataItem endpoint of bundling service, `{id}` is replaced by DataItem ID
+        #[arg(long)]
+        url: String,
+    },
+    /// Print fee quoted by gateway for storing given number of bytes
+    Price {
+        /// Size of transaction data in bytes
+        bytes: u64,
+    },
+    /// Check that previously written JSON dump deserializes into DataItems, without network access
+    Validate {
+        /// JSON dump file
+        #[arg(long, value_name = "FILE")]
+        input: PathBuf,
+
+        /// Recompute DataItem ids and verify signatures too
+        #[arg(long)]
+        verify: bool,
+    },
+}
 
 /// Transaction bundle dumper from Arweave network
 #[derive(Parser, Debug)]
-#[command(about, long_about = None)]
+#[command(about, long_about = None, subcommand_negates_reqs = true)]
 struct Args {
-    /// Transaction ID to fetch
-    #[arg(short, long)]
-    transaction_id: Base64,
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// Transaction ID to fetch, can be given multiple times
+    #[arg(short, long, required_unless_present_any = ["prefix", "follow", "input_file", "id_file"], value_parser = arweave::parse_transaction_id)]
+    transaction_id: Vec<Base64>,
+
+    /// Read transaction IDs to fetch from given file, one per line. Blank lines and `#` comments are skipped
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["prefix", "follow", "input_file"])]
+    id_file: Option<String>,
+
+    /// Parse raw bundle data from given file instead of fetching transaction, `-` reads from stdin
+    #[arg(long, conflicts_with_all = ["transaction_id", "prefix", "follow", "count", "max_size"])]
+    input_file: Option<String>,
+
+    /// Number of transactions dumped concurrently
+    #[arg(long, short, default_value_t = 1, value_parser = RangedU64ValueParser::<usize>::new().range(1..))]
+    jobs: usize,
+
+    /// Look up bundle transaction by ID prefix via GraphQL instead of full transaction ID
+    #[arg(long, conflicts_with = "transaction_id")]
+    prefix: Option<String>,
+
+    /// Treat given transactions as indexes and dump child bundles they reference
+    /// by path manifest data or --index-tag tags
+    #[arg(long, conflicts_with_all = ["follow", "input_file"])]
+    expand_index: bool,
+
+    /// Tag of index transaction referencing child bundle transaction
+    #[arg(long, default_value = index::DEFAULT_INDEX_TAG, requires = "expand_index")]
+    index_tag: String,
+
+    /// Number of index manifests fetched concurrently, referenced child bundles keep order of their indexes
+    /// and are dumped afterwards with --jobs concurrency
+    #[arg(long, default_value_t = 1, value_parser = RangedU64ValueParser::<usize>::new().range(1..), requires = "expand_index")]
+    expand_jobs: usize,
+
+    /// Dump only child bundles mined at given block height or later, e.g. last height of previous run
+    #[arg(long, value_name = "HEIGHT", requires = "expand_index")]
+    since_height: Option<u64>,
+
+    /// Dump only child bundles mined at given block height or earlier
+    #[arg(long, value_name = "HEIGHT", requires = "expand_index")]
+    until_height: Option<u64>,
+
+    /// Restrict prefix lookup or followed transactions to given owner addresses
+    #[arg(long)]
+    owner: Vec<String>,
+
+    /// Number of GraphQL result pages to scan during prefix lookup or follow poll
+    #[arg(long, default_value_t = 10)]
+    prefix_scan_pages: usize,
+
+    /// Keep polling for new bundle transactions and dump each into its own file
+    #[arg(long, conflicts_with_all = ["transaction_id", "prefix", "output_file", "count", "stats_file", "tags_file", "tags_triples"])]
+    follow: bool,
+
+    /// Poll interval of --follow mode
+    #[arg(long, default_value = "30s", requires = "follow")]
+    interval: humantime::Duration,
+
+    /// Tag filter of followed transactions in NAME=VALUE format
+    #[arg(long, requires = "follow")]
+    query: Vec<String>,
 
     /// Arweave API base url
     #[arg(long, default_value_t = arweave_rs::consts::ARWEAVE_BASE_URL.to_string())]
     base_url: String,
 
-    /// JSON output file name. Default name: <transaction_ID>.json
+    /// Maximum number of HTTP redirects to follow
+    #[arg(long, default_value_t = arweave::DEFAULT_MAX_REDIRECTS)]
+    max_redirects: usize,
+
+    /// Do not follow HTTP redirects, fail on them instead
+    #[arg(long, conflicts_with = "max_redirects")]
+    no_redirects: bool,
+
+    /// Do not send Referer header when following redirects
+    #[arg(long)]
+    no_referer: bool,
+
+    /// Number of chunks fetched ahead while parsing and writing catches up
+    #[arg(long, default_value_t = arweave::DEFAULT_CHUNK_BUFFER, value_parser = RangedU64ValueParser::<usize>::new().range(1..))]
+    chunk_buffer: usize,
+
+    /// Persist fetched transaction data (into <FILE>.data) and offset of the next chunk into given state file,
+    /// both are removed once all chunks are fetched
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["input_file", "follow"])]
+    chunk_state: Option<PathBuf>,
+
+    /// Continue download after the last chunk persisted in --chunk-state file instead of starting over
+    #[arg(long, requires = "chunk_state")]
+    resume: bool,
+
+    /// Decompress gzip chunk responses of gateways which do not label them with Content-Encoding header
+    #[arg(long)]
+    sniff_compression: bool,
+
+    /// Retries of chunk missing from gateway, freshly posted bundles may be not fully seeded yet
+    #[arg(long, default_value_t = 0)]
+    seed_retries: u32,
+
+    /// Cap on seed and pending chunk retries of all dumped transactions together,
+    /// once it is used up chunks fail without retries
+    #[arg(long)]
+    max_total_retries: Option<u64>,
+
+    /// Delay before first seed retry, doubled on every next one up to 5 minutes
+    #[arg(long, default_value = "5s")]
+    seed_retry_delay: humantime::Duration,
+
+    /// How long chunk which gateway reports as pending (202) is retried
+    #[arg(long, default_value = "60s")]
+    pending_chunk_timeout: humantime::Duration,
+
+    /// Keep up to given number of transaction metadata and offset responses in memory,
+    /// so that transactions visited again (--follow, index manifests, multiple IDs) are not re-fetched
+    #[arg(long, value_name = "ENTRIES")]
+    metadata_cache: Option<usize>,
+
+    /// Size in bytes of read buffer between transaction data source and bundle parser
+    #[arg(long, default_value_t = DEFAULT_READ_BUFFER, value_parser = RangedU64ValueParser::<usize>::new().range(1..))]
+    read_buffer: usize,
+
+    /// Size in bytes of write buffer of output and tags files
+    #[arg(long, default_value_t = DEFAULT_WRITE_BUFFER, value_parser = RangedU64ValueParser::<usize>::new().range(1..))]
+    write_buffer: usize,
+
+    /// How transaction data is retrieved from the gateway
+    #[arg(long, value_enum, default_value_t = DataSource::Chunks)]
+    data_source: DataSource,
+
+    /// Extension for `served` data source, data is fetched from /tx/{id}/data.<ext> instead of /{id}
+    #[arg(long)]
+    served_ext: Option<String>,
+
+    /// Output format
+    #[arg(long, value_enum, default_value_t = OutputFormat::Json)]
+    format: OutputFormat,
+
+    /// Tag whose value is written as last CSV column
+    #[cfg(feature = "csv")]
+    #[arg(long)]
+    csv_tag: Option<String>,
+
+    /// Output file name. Default name: <transaction_ID>.<format>
     #[arg(long, short)]
     output_file: Option<String>,
+
+    /// Write JSON array of DataItems to stdout, other output goes to stderr
+    #[arg(long, conflicts_with_all = ["output_file", "split", "follow"])]
+    stdout: bool,
+
+    /// Do not print informational messages to stderr, warnings and errors are still printed
+    #[arg(long, short)]
+    quiet: bool,
+
+    /// Flush and fsync output file every N items
+    #[arg(long, value_parser = RangedU64ValueParser::<usize>::new().range(1..))]
+    fsync_interval: Option<usize>,
+
+    /// Embed data of `application/json` DataItems as parsed JSON instead of base64 string
+    #[arg(long)]
+    inline_json: bool,
+
+    /// Maximal data size of DataItem embedded by --inline-json
+    #[arg(long, default_value_t = output::DEFAULT_INLINE_JSON_MAX_SIZE, requires = "inline_json")]
+    inline_json_max_size: usize,
+
+    /// Write anchor as string instead of base64 when it is UTF-8 text
+    #[arg(long)]
+    text_anchor: bool,
+
+    /// Write only tags whose name starts with given prefix, tags as read are kept in `original_tags`
+    #[arg(long, value_name = "PREFIX")]
+    only_tags_prefix: Option<String>,
+
+    /// Strip given prefix (e.g. `Custom:`) from tag names having it, tags as read are kept in `original_tags`
+    #[arg(long, value_name = "PREFIX")]
+    flatten_tags_prefix: Option<String>,
+
+    /// Write `index` of every DataItem in its bundle with `offset` and `length` of its bytes in bundle data
+    #[arg(long)]
+    with_offsets: bool,
+
+    /// Rename DataItem JSON fields on output, comma separated FROM=TO pairs, e.g. `bundle_id=id,owner_public_key=owner`
+    #[arg(long, value_name = "MAPPING", value_delimiter = ',', value_parser = parse_field_rename)]
+    rename_fields: Vec<(String, String)>,
+
+    /// Write only first N bytes of DataItem data as base64, with `data_truncated` flag when cut
+    /// and `data_len` holding the whole data size
+    #[arg(long, value_name = "BYTES")]
+    data_preview: Option<usize>,
+
+    /// Write data of DataItems bigger than given number of bytes into <output_file>.<DataItem_ID>.data files
+    /// instead of the JSON, which references them in `data_file` field
+    #[arg(long)]
+    spill_threshold: Option<usize>,
+
+    /// Skip malformed DataItems with a warning instead of failing the whole dump
+    #[arg(long, conflicts_with = "strict")]
+    skip_invalid_items: bool,
+
+    /// Add hex encoded SHA-256 of DataItem data as `data_sha256` field, computed while data is read
+    #[arg(long)]
+    hash_data: bool,
+
+    /// Maximal number of DataItem data bytes held in memory at once, across all concurrently dumped transactions
+    #[arg(long)]
+    mem_budget: Option<usize>,
+
+    /// Budget created from --mem-budget, shared by all dumps
+    #[arg(skip)]
+    shared_mem_budget: Option<MemoryBudget>,
+
+    /// Cancel the whole dump after given time, e.g. 5m. Output written so far is closed cleanly
+    #[arg(long)]
+    deadline: Option<humantime::Duration>,
+
+    /// Abandon single gateway request taking longer than given time, e.g. 30s, while --deadline
+    /// still limits the whole dump
+    #[arg(long)]
+    request_timeout: Option<humantime::Duration>,
+
+    /// Instant of --deadline, counted from the start
+    #[arg(skip)]
+    deadline_at: Option<tokio::time::Instant>,
+
+    /// Cancelled on Ctrl-C, shared by all dumps
+    #[arg(skip)]
+    interrupted: CancellationToken,
+
+    /// Also write DataItem ids with their tags as NDJSON into given file
+    #[arg(long)]
+    tags_file: Option<String>,
+
+    /// Also write every tag as `{"id": ..., "name": ..., "value": ...}` NDJSON line into given file
+    #[arg(long, value_name = "FILE")]
+    tags_triples: Option<String>,
+
+    /// Write DataItems as compact JSON
+    #[arg(long)]
+    compact: bool,
+
+    /// Keep one tag per line in --compact output
+    #[arg(long, requires = "compact")]
+    readable_tags: bool,
+
+    /// Write each DataItem into its own <output_file>.<index>.json file, index is zero padded to 6 digits
+    #[arg(long)]
+    split: bool,
+
+    /// Write JSON object with `_format_version` and `bundle_tags` of the bundle transaction, DataItems go into its `items` array
+    #[arg(long, conflicts_with_all = ["split", "tar"])]
+    wrap: bool,
+
+    /// Add `dumped_at` RFC 3339 timestamp and `gateway` URL which served the bundle to --wrap object
+    #[arg(long, requires = "wrap")]
+    provenance: bool,
+
+    /// Precede JSON array with `// _format_version: N` comment line, --wrap object has the field instead
+    #[arg(long, conflicts_with_all = ["split", "tar", "wrap"])]
+    version_comment: bool,
+
+    /// Write DataItems in reverse bundle order. All DataItems are held in memory until the bundle is parsed,
+    /// use --spill-threshold to keep large data on disk meanwhile. Not bounded by --mem-budget
+    #[arg(long, conflicts_with = "mem_budget")]
+    reverse: bool,
+
+    /// Write data of each DataItem as an entry of given tar archive, named by DataItem ID and
+    /// Content-Type extension, followed by manifest.json entry with IDs and tags
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["output_file", "split", "stdout", "format", "follow"])]
+    tar: Option<String>,
+
+    /// Remove incomplete output when dump fails. Output is written into <output>.tmp and renamed once complete,
+    /// by default incomplete one is left for inspection
+    #[arg(long)]
+    remove_partial: bool,
+
+    /// Print number of DataItems in the bundle and exit, prefixed by transaction ID with multiple transactions
+    #[arg(long)]
+    count: bool,
+
+    /// Set when multiple transactions are dumped, so that --count lines tell which one they are for
+    #[arg(skip)]
+    label_counts: bool,
+
+    /// Abort if transaction data is larger than given number of bytes
+    #[arg(long)]
+    max_size: Option<usize>,
+
+    /// Dump transaction even if it exceeds --max-size
+    #[arg(long, requires = "max_size")]
+    force: bool,
+
+    /// Abort before reading any DataItem if bundle table declares one larger than given number of bytes
+    #[arg(long, value_name = "BYTES")]
+    max_item_size: Option<usize>,
+
+    /// Write dump statistics as JSON into given file
+    #[arg(long)]
+    stats_file: Option<String>,
+
+    /// Tag whose values DataItems are counted by in stats, e.g. to see which apps they come from
+    #[arg(long, value_name = "NAME", default_value = stats::DEFAULT_GROUP_TAG)]
+    stats_group_tag: String,
+
+    /// With multiple transaction IDs failures, if any, are summarized into given file,
+    /// as JSON array of objects like --json-errors ones. With --follow it lists dumps given up
+    /// after failing on 3 more polls, the most recent 1000 of them
+    #[arg(long, default_value = "errors.json")]
+    errors_file: String,
+
+    /// Fail if any DataItem id does not match sha256 of its signature
+    #[arg(long)]
+    verify_ids: bool,
+
+    /// Fail if signature of any arweave, ed25519 or solana DataItem is invalid.
+    /// Other signature types and spilled DataItems are skipped with a note
+    #[arg(long)]
+    verify: bool,
+
+    /// What to do when --verify finds invalid signature
+    #[arg(long, value_enum, default_value = "fail-fast", requires = "verify")]
+    verify_mode: VerifyMode,
+
+    /// Fail if DataItems of the bundle do not all share the same signature type
+    #[arg(long)]
+    uniform_sig: bool,
+
+    /// Warn on stderr about DataItems with repeated tag names
+    #[arg(long)]
+    warn_duplicate_tags: bool,
+
+    /// Write only DataItems having tag with given name, any value. Can be given multiple times, all must be present
+    #[arg(long, value_name = "NAME")]
+    has_tag: Vec<String>,
+
+    /// Write only DataItems of given signature type (arweave, ed25519, ethereum, solana)
+    #[arg(long, value_name = "NAME", value_parser = parse_signature_scheme)]
+    only_signature_type: Option<SignatureScheme>,
+
+    /// Write only DataItems whose Content-Type tag matches any of comma separated patterns,
+    /// e.g. `image/*,video/*`. Wildcard is allowed only at the end of pattern
+    #[arg(long, value_name = "PATTERNS", value_delimiter = ',', value_parser = parse_content_type_pattern)]
+    content_type: Vec<ContentTypePattern>,
+
+    /// Verify that bundle header and DataItem table match transaction data size before parsing items,
+    /// fail on any oddity otherwise only warned about (empty bundle, repeated tag names, trailing data)
+    #[arg(long)]
+    strict: bool,
+
+    /// Report errors as JSON on stderr with exit code mapped to the error kind
+    #[arg(long)]
+    json_errors: bool,
+
+    /// Serve Prometheus metrics on given address, e.g. 127.0.0.1:9100
+    #[cfg(feature = "metrics")]
+    #[arg(long)]
+    metrics_addr: Option<std::net::SocketAddr>,
+
+    /// Stream JSON output into S3 object given as s3://<bucket>/<key> by multipart upload,
+    /// credentials and endpoint are taken from standard AWS environment variables
+    #[cfg(feature = "s3")]
+    #[arg(long, value_name = "URL", conflicts_with_all = ["output_file", "split", "tar", "stdout", "spill_threshold", "fsync_interval", "follow"])]
+    output: Option<String>,
+}
+
+impl Args {
+    // S3 object of --output
+    fn s3_output(&self) -> Option<&str> {
+        #[cfg(feature = "s3")]
+        return self.output.as_deref();
+        #[cfg(not(feature = "s3"))]
+        return None;
+    }
 }
 
 #[tokio::main]
-async fn main() -> anyhow::Result<()> {
-    let Args {
-        transaction_id,
-        base_url,
-        output_file,
-    } = Args::try_parse()?;
+async fn main() -> anyhow::Result<ExitCode> {
+    let args = Args::try_parse()?;
+    if !args.json_errors {
+        return run(args).await.map(|_| ExitCode::SUCCESS);
+    }
+
+    let transaction_id = match args.transaction_id.as_slice() {
+        [transaction_id] => Some(transaction_id.to_string()),
+        _ => None,
+    };
+    match run(args).await {
+        Ok(()) => Ok(ExitCode::SUCCESS),
+        Err(err) => {
+            let report = ErrorReport::new(&err, transaction_id);
+            eprintln!("{}", serde_json::to_string(&report)?);
+            Ok(ExitCode::from(report.error.exit_code()))
+        }
+    }
+}
+
+async fn run(mut args: Args) -> anyhow::Result<()> {
+    args.shared_mem_budget = args.mem_budget.map(MemoryBudget::new).transpose()?;
+    args.deadline_at = args
+        .deadline
+        .map(|deadline| tokio::time::Instant::now() + *deadline);
+    let interrupted = args.interrupted.clone();
+    tokio::spawn(async move {
+        if tokio::signal::ctrl_c().await.is_ok() {
+            eprintln!("Interrupted, closing output. Press Ctrl-C again to abort right away");
+            interrupted.cancel();
+        }
+        if tokio::signal::ctrl_c().await.is_ok() {
+            std::process::exit(130);
+        }
+    });
+    let mut client_builder = arweave::Client::builder(&args.base_url);
+    if let Some(Command::DataItem { url, .. }) = &args.command {
+        client_builder = client_builder.data_item_url(url);
+    }
+    client_builder = client_builder
+        .max_redirects(args.max_redirects)
+        .follow_redirects(!args.no_redirects)
+        .referer(!args.no_referer)
+        .seed_retries(args.seed_retries)
+        .seed_retry_delay(args.seed_retry_delay.into())
+        .pending_chunk_timeout(args.pending_chunk_timeout.into())
+        .sniff_compression(args.sniff_compression)
+        .quiet(args.quiet);
+    if let Some(max_total_retries) = args.max_total_retries {
+        client_builder = client_builder.max_total_retries(max_total_retries);
+    }
+    if let Some(request_timeout) = args.request_timeout {
+        client_builder = client_builder.timeout(request_timeout.into());
+    }
+    let mut arweave_client = client_builder.build()?;
+    if let Some(capacity) = args.metadata_cache {
+        arweave_client = arweave_client.with_metadata_cache(capacity);
+    }
+
+    #[cfg(feature = "metrics")]
+    if let Some(metrics_addr) = args.metrics_addr {
+        let listener = tokio::net::TcpListener::bind(metrics_addr)
+            .await
+            .with_context(|| format!("Failed to bind metrics address {metrics_addr}"))?;
+        tokio::spawn(async move {
+            if let Err(err) = metrics::serve(listener).await {
+                eprintln!("Metrics server failed: {err:#}");
+            }
+        });
+    }
+
+    if let Some(command) = &args.command {
+        return unless_cancelled(&args, run_command(&arweave_client, command)).await;
+    }
+
+    if args.follow {
+        return follow(&arweave_client, &args).await;
+    }
+
+    if let Some(input_file) = &args.input_file {
+        return dump_input_file(input_file, &args).await;
+    }
+
+    let transaction_ids =
+        unless_cancelled(&args, resolve_transaction_ids(&arweave_client, &args)).await?;
+    if let [transaction_id] = transaction_ids.as_slice() {
+        return dump_transaction(&arweave_client, transaction_id, &args).await;
+    }
+
+    check_multiple_transactions_options(&args)?;
+    args.label_counts = true;
+    dump_transactions(&arweave_client, &transaction_ids, &args).await
+}
+
+// options naming single output file would be overwritten by every transaction
+fn check_multiple_transactions_options(args: &Args) -> anyhow::Result<()> {
+    if args.output_file.is_some()
+        || args.stats_file.is_some()
+        || args.stdout
+        || args.tar.is_some()
+        || args.s3_output().is_some()
+        || args.chunk_state.is_some()
+        || args.tags_file.is_some()
+        || args.tags_triples.is_some()
+    {
+        return Err(anyhow::anyhow!(
+            "--output-file, --stats-file, --stdout, --tar, --output, --chunk-state, --tags-file and --tags-triples can not be used with multiple transaction IDs"
+        ));
+    }
+    Ok(())
+}
+
+// transaction IDs given by options, indexes are replaced by bundles they reference
+async fn resolve_transaction_ids(
+    arweave_client: &arweave::Client,
+    args: &Args,
+) -> anyhow::Result<Vec<Base64>> {
+    let mut transaction_ids = match &args.prefix {
+        Some(prefix) => vec![
+            resolve_prefix(
+                arweave_client,
+                prefix,
+                args.owner.clone(),
+                args.prefix_scan_pages,
+            )
+            .await?,
+        ],
+        None => args.transaction_id.clone(),
+    };
+    if let Some(id_file) = &args.id_file {
+        let text = tokio::fs::read_to_string(id_file)
+            .await
+            .with_context(|| format!("Failed to read ID file: {id_file}"))?;
+        let mut ids = arweave::parse_transaction_id_list(&text)
+            .with_context(|| format!("Invalid ID file: {id_file}"))?;
+        // ID file is deduplicated already, only the few IDs given by -t are left to skip
+        ids.retain(|id| !transaction_ids.contains(id));
+        transaction_ids.extend(ids);
+    }
+    if transaction_ids.is_empty() {
+        return Err(anyhow::anyhow!("No transaction IDs to dump"));
+    }
+
+    if args.expand_index {
+        let mut child_ids = vec![];
+        let mut seen = HashSet::new();
+        // completed manifests are buffered until manifests of preceding indexes are in
+        let index_tag = &args.index_tag;
+        let mut manifests = futures_util::stream::iter(transaction_ids)
+            .map(|index_id| async move {
+                let manifest = arweave_client
+                    .fetch_index_manifest(&index_id, index_tag)
+                    .await;
+                (index_id, manifest)
+            })
+            .buffered(args.expand_jobs);
+        while let Some((index_id, manifest)) = manifests.next().await {
+            let manifest = manifest?;
+            info!(
+                args,
+                "Index {index_id} references {} bundles",
+                manifest.ids.len()
+            );
+            for id in manifest.ids {
+                if seen.insert(id.0.clone()) {
+                    child_ids.push(id);
+                }
+            }
+        }
+        if args.since_height.is_some() || args.until_height.is_some() {
+            let block = BlockFilter {
+                min: args.since_height,
+                max: args.until_height,
+            };
+            let referenced = child_ids.len();
+            child_ids = arweave_client
+                .filter_by_block_height(&child_ids, block)
+                .await?;
+            info!(
+                args,
+                "{} of {referenced} referenced bundles are within block heights",
+                child_ids.len()
+            );
+        }
+        transaction_ids = child_ids;
+    }
+    Ok(transaction_ids)
+}
+
+async fn run_command(arweave_client: &arweave::Client, command: &Command) -> anyhow::Result<()> {
+    match command {
+        Command::Item {
+            transaction_id,
+            index,
+        } => {
+            let data_item = arweave_client
+                .fetch_bundle_item(transaction_id, *index)
+                .await?;
+            print_item(&data_item, Some(*index))
+        }
+        Command::DataItem { id, .. } => {
+            let data_item = arweave_client.fetch_data_item(id).await?;
+            print_item(&data_item, None)
+        }
+        Command::Price { bytes } => {
+            let winston = arweave_client.fetch_price(*bytes).await?;
+            println!("{winston} winston ({})", format_ar(winston));
+            Ok(())
+        }
+        Command::Validate { input, verify } => validate(input, *verify).await,
+    }
+}
+
+// failure of one transaction does not stop the others
+async fn dump_transactions(
+    arweave_client: &arweave::Client,
+    transaction_ids: &[Base64],
+    args: &Args,
+) -> anyhow::Result<()> {
+    let results = futures_util::stream::iter(transaction_ids)
+        .map(|transaction_id| async move {
+            let res = dump_transaction(arweave_client, transaction_id, args).await;
+            (transaction_id, res)
+        })
+        .buffer_unordered(args.jobs)
+        .collect::<Vec<_>>()
+        .await;
+
+    let failed = results
+        .iter()
+        .filter_map(|(transaction_id, res)| res.as_ref().err().map(|err| (transaction_id, err)))
+        .collect::<Vec<_>>();
+
+    info!(args);
+    info!(
+        args,
+        "Dumped {} of {} transactions",
+        results.len() - failed.len(),
+        results.len()
+    );
+    for (transaction_id, err) in &failed {
+        eprintln!("[{transaction_id}] failed: {err:#}");
+    }
+    if failed.is_empty() {
+        return Ok(());
+    }
+    // --count prints to stdout only, failures are reported above
+    if !args.count {
+        let error_reports = failed
+            .iter()
+            .map(|(transaction_id, err)| ErrorReport::new(err, Some(transaction_id.to_string())))
+            .collect::<Vec<_>>();
+        tokio::fs::write(
+            &args.errors_file,
+            serde_json::to_vec_pretty(&error_reports)?,
+        )
+        .await?;
+        info!(args, "Errors stored in: {}", args.errors_file);
+    }
+    Err(anyhow::anyhow!(
+        "{} of {} transactions failed",
+        failed.len(),
+        results.len()
+    ))
+}
 
-    let arweave_client = arweave::Client::new(&base_url)?;
+async fn dump_transaction(
+    arweave_client: &arweave::Client,
+    transaction_id: &Base64,
+    args: &Args,
+) -> anyhow::Result<()> {
+    let opened = unless_cancelled(args, open_transaction(arweave_client, transaction_id, args));
+    let Some((tx, data_reader, expected_size)) = opened.await? else {
+        return Ok(());
+    };
+    dump_reader(
+        data_reader,
+        expected_size,
+        transaction_id.to_string(),
+        Some(&tx),
+        args,
+    )
+    .await
+}
 
-    let tx = arweave_client.fetch_transaction(&transaction_id).await?;
+// metadata and data reader of the bundle with its expected size for --strict,
+// `None` when there is nothing to dump, i.e. --count printed the DataItem count
+async fn open_transaction(
+    arweave_client: &arweave::Client,
+    transaction_id: &Base64,
+    args: &Args,
+) -> anyhow::Result<Option<(TxMetadata, Box<dyn AsyncRead + Unpin>, Option<usize>)>> {
+    let tx = arweave_client.fetch_transaction(transaction_id).await?;
 
     if !tx.is_bundle() {
+        return Err(Error::NotABundle {
+            transaction_id: transaction_id.to_string(),
+        }
+        .into());
+    }
+
+    if args.count {
+        let item_count = arweave_client
+            .fetch_bundle_item_count(transaction_id)
+            .await?;
+        match args.label_counts {
+            true => println!("{transaction_id} {item_count}"),
+            false => println!("{item_count}"),
+        }
+        return Ok(None);
+    }
+
+    if let Some(max_size) = args.max_size {
+        let tx_offset_data = arweave_client
+            .fetch_transaction_offset(transaction_id)
+            .await?;
+        if tx_offset_data.size > max_size {
+            if !args.force {
+                return Err(anyhow::Error::new(Error::TooLarge {
+                    size: tx_offset_data.size,
+                    max_size,
+                })
+                .context("use --force to dump anyway"));
+            }
+            info!(
+                args,
+                "Transaction data size {} exceeds --max-size {max_size}, dumping anyway",
+                tx_offset_data.size
+            );
+        }
+    }
+
+    let mut expected_size = None;
+    let data_reader: Box<dyn AsyncRead + Unpin> = match args.data_source {
+        DataSource::Chunks => {
+            if args.strict {
+                let tx_offset_data = arweave_client
+                    .fetch_transaction_offset(transaction_id)
+                    .await?;
+                expected_size = Some(tx_offset_data.size);
+            }
+            let chunk_stream: LocalBoxStream<'_, anyhow::Result<Bytes>> = match &args.chunk_state {
+                Some(state_file) => arweave_client
+                    .transaction_data_chunk_stream_with_state(
+                        transaction_id,
+                        args.chunk_buffer,
+                        ChunkStateOptions {
+                            state_file: state_file.clone(),
+                            resume: args.resume,
+                        },
+                    )
+                    .boxed_local(),
+                None => arweave_client
+                    .transaction_data_chunk_stream_with_buffer(transaction_id, args.chunk_buffer)
+                    .boxed_local(),
+            };
+            // FIXME: little hack to get back to io::Error from general anyhow::Error to make stream_reader happy
+            let chunk_stream = chunk_stream.map_err(error::into_io_error);
+            Box::new(StreamReader::new(chunk_stream))
+        }
+        DataSource::Served if args.chunk_state.is_some() => {
+            return Err(anyhow::anyhow!("--chunk-state needs chunks data source"));
+        }
+        DataSource::Served => {
+            let data = arweave_client
+                .fetch_served_data(transaction_id, args.served_ext.as_deref())
+                .await?;
+            if args.strict {
+                expected_size = Some(data.len());
+            }
+            Box::new(Cursor::new(data))
+        }
+    };
+    Ok(Some((tx, data_reader, expected_size)))
+}
+
+/// Output file written under `<name>.tmp` and renamed to its name once complete,
+/// so that the final path never holds partial output.
+struct PartialOutput {
+    path: String,
+    tmp_path: String,
+    remove_on_failure: bool,
+    completed: bool,
+}
+
+impl PartialOutput {
+    fn new(path: &str, remove_on_failure: bool) -> Self {
+        Self {
+            path: path.to_string(),
+            tmp_path: format!("{path}.tmp"),
+            remove_on_failure,
+            completed: false,
+        }
+    }
+
+    async fn complete(mut self) -> anyhow::Result<()> {
+        tokio::fs::rename(&self.tmp_path, &self.path)
+            .await
+            .with_context(|| format!("Failed to rename {} to {}", self.tmp_path, self.path))?;
+        self.completed = true;
+        Ok(())
+    }
+}
+
+// dump failed if output was not completed
+impl Drop for PartialOutput {
+    fn drop(&mut self) {
+        if self.completed {
+            return;
+        }
+        if self.remove_on_failure {
+            let _ = std::fs::remove_file(&self.tmp_path);
+        } else {
+            eprintln!("Incomplete output left in {}", self.tmp_path);
+        }
+    }
+}
+
+fn json_array_sink<W>(
+    writer: W,
+    output_options: OutputOptions,
+    tx: Option<&TxMetadata>,
+    args: &Args,
+) -> anyhow::Result<JsonArraySink<W>> {
+    let json_format = match (args.compact, args.readable_tags) {
+        (false, _) => JsonFormat::Pretty,
+        (true, false) => JsonFormat::Compact,
+        (true, true) => JsonFormat::CompactReadableTags,
+    };
+    let sink = JsonArraySink::new(writer, json_format, output_options);
+    Ok(match (args.wrap, args.version_comment) {
+        (true, _) => sink.with_header(output_header(tx, args)?),
+        (false, true) => sink.with_version_comment(),
+        (false, false) => sink,
+    })
+}
+
+#[cfg(feature = "s3")]
+async fn s3_sink(
+    url: &str,
+    output_options: OutputOptions,
+    tx: Option<&TxMetadata>,
+    args: &Args,
+) -> anyhow::Result<Box<dyn OutputSink>> {
+    let location = s3::S3Location::parse(url)?;
+    let upload = s3::MultipartUpload::create(s3::S3Config::from_env()?, &location).await?;
+    let json = json_array_sink(s3::PartWriter::new(upload), output_options, tx, args)?;
+    Ok(Box::new(s3::S3Sink::new(json)))
+}
+
+#[cfg(not(feature = "s3"))]
+async fn s3_sink(
+    _url: &str,
+    _output_options: OutputOptions,
+    _tx: Option<&TxMetadata>,
+    _args: &Args,
+) -> anyhow::Result<Box<dyn OutputSink>> {
+    Err(anyhow::anyhow!("S3 output needs s3 feature"))
+}
+
+// fields of --wrap object preceding DataItems, bundle read from input file has no gateway
+fn output_header(
+    tx: Option<&TxMetadata>,
+    args: &Args,
+) -> anyhow::Result<serde_json::Map<String, serde_json::Value>> {
+    let mut header = serde_json::Map::new();
+    if let Some(tx) = tx {
+        header.insert("bundle_tags".into(), serde_json::to_value(tx.tag_map())?);
+    }
+    if args.provenance {
+        let dumped_at = humantime::format_rfc3339_seconds(std::time::SystemTime::now());
+        header.insert("dumped_at".into(), dumped_at.to_string().into());
+        if tx.is_some() {
+            header.insert("gateway".into(), args.base_url.clone().into());
+        }
+    }
+    Ok(header)
+}
+
+fn parse_signature_scheme(name: &str) -> Result<SignatureScheme, String> {
+    SignatureScheme::from_name(name).ok_or_else(|| {
+        let names: Vec<_> = SignatureScheme::ALL
+            .iter()
+            .map(|scheme| scheme.name())
+            .collect();
+        format!(
+            "unknown signature type, expected one of: {}",
+            names.join(", ")
+        )
+    })
+}
+
+fn parse_field_rename(mapping: &str) -> Result<(String, String), String> {
+    match mapping.split_once('=') {
+        Some((from, to)) if !from.is_empty() && !to.is_empty() => {
+            Ok((from.to_string(), to.to_string()))
+        }
+        _ => Err(format!("expected FROM=TO, got '{mapping}'")),
+    }
+}
+
+fn parse_content_type_pattern(pattern: &str) -> Result<ContentTypePattern, String> {
+    ContentTypePattern::new(pattern).map_err(|err| err.to_string())
+}
+
+// 1 AR is 10^12 winston
+fn format_ar(winston: u64) -> String {
+    const WINSTON_PER_AR: u64 = 1_000_000_000_000;
+    format!(
+        "{}.{:012} AR",
+        winston / WINSTON_PER_AR,
+        winston % WINSTON_PER_AR
+    )
+}
+
+fn print_item(data_item: &DataItem, index: Option<usize>) -> anyhow::Result<()> {
+    let mut value = serde_json::to_value(data_item)?;
+    if let Some(object) = value.as_object_mut() {
+        object.remove("data");
+        if let Some(index) = index {
+            object.insert("index".into(), index.into());
+        }
+        object.insert("data_len".into(), data_item.data_len().into());
+    }
+    println!("{}", serde_json::to_string_pretty(&value)?);
+    Ok(())
+}
+
+async fn validate(input: &PathBuf, verify: bool) -> anyhow::Result<()> {
+    let dump = tokio::fs::read(input)
+        .await
+        .with_context(|| format!("Failed to read dump: {}", input.display()))?;
+    let report = dump::validate_dump_json(&dump, verify)
+        .with_context(|| format!("Invalid dump: {}", input.display()))?;
+    for (index, error) in &report.errors {
+        eprintln!("DataItem {index}: {error}");
+    }
+    println!("{} DataItems", report.item_count);
+    if report.unverified_count > 0 {
+        eprintln!(
+            "WARNING: signatures of {} DataItems could not be verified",
+            report.unverified_count
+        );
+    }
+    if !report.errors.is_empty() {
         return Err(anyhow::anyhow!(
-            "Given transacion by ID is not ANS-104 bundle"
+            "{} of {} DataItems are invalid",
+            report.errors.len(),
+            report.item_count
         ));
     }
+    Ok(())
+}
 
-    let chunk_stream = arweave_client
-        .transaction_data_chunk_stream(&transaction_id)
-        // FIXME: little hack to get back to io::Error from general anyhow::Error to make stream_reader happy
-        .map_err(std::io::Error::other);
+async fn dump_input_file(input_file: &str, args: &Args) -> anyhow::Result<()> {
+    if input_file == "-" {
+        if args.strict {
+            return Err(anyhow::anyhow!(
+                "--strict needs known data size which is not available for stdin"
+            ));
+        }
+        if args.output_file.is_none() && !args.stdout {
+            return Err(anyhow::anyhow!(
+                "--output-file or --stdout is required when reading from stdin"
+            ));
+        }
+        return dump_reader(
+            Box::new(tokio::io::stdin()),
+            None,
+            "stdin".to_string(),
+            None,
+            args,
+        )
+        .await;
+    }
 
-    let stream_reader = StreamReader::new(chunk_stream);
-    pin_mut!(stream_reader);
+    let file = tokio::fs::File::open(input_file)
+        .await
+        .with_context(|| format!("Failed to open input file: {input_file}"))?;
+    let expected_size = match args.strict {
+        true => Some(usize::try_from(file.metadata().await?.len())?),
+        false => None,
+    };
+    dump_reader(
+        Box::new(file),
+        expected_size,
+        input_file.to_string(),
+        None,
+        args,
+    )
+    .await
+}
 
-    let data_item_stream = bundle::ans104_bundle_data_item_stream(stream_reader);
-    pin_mut!(data_item_stream);
+// parses bundle from reader and writes DataItems as configured by args,
+// output is written to <default_name>.<format> unless --output-file is given,
+// `tx` is metadata of the bundle transaction unless bundle comes from input file
+async fn dump_reader(
+    data_reader: Box<dyn AsyncRead + Unpin>,
+    expected_size: Option<usize>,
+    default_name: String,
+    tx: Option<&TxMetadata>,
+    args: &Args,
+) -> anyhow::Result<()> {
+    if args.split && !matches!(args.format, OutputFormat::Json) {
+        return Err(anyhow::anyhow!("--split is supported for JSON format only"));
+    }
+    if args.wrap && !matches!(args.format, OutputFormat::Json) {
+        return Err(anyhow::anyhow!("--wrap is supported for JSON format only"));
+    }
+    if args.version_comment && !matches!(args.format, OutputFormat::Json) {
+        return Err(anyhow::anyhow!(
+            "--version-comment is supported for JSON format only"
+        ));
+    }
+    if args.s3_output().is_some() && !matches!(args.format, OutputFormat::Json) {
+        return Err(anyhow::anyhow!(
+            "--output is supported for JSON format only"
+        ));
+    }
+    let filename = args
+        .s3_output()
+        .map(str::to_string)
+        .or_else(|| args.tar.clone())
+        .or_else(|| args.output_file.clone())
+        .unwrap_or_else(|| format!("{default_name}.{}", args.format.extension()));
+    // in split mode every DataItem goes into its own <prefix>.<index>.json file instead,
+    // spilled data goes into <prefix>.<DataItem_ID>.data files
+    let output_prefix = filename.trim_end_matches(".json").to_string();
 
-    let filename = output_file.unwrap_or_else(|| format! {"{transaction_id}.json"});
-    let writer = tokio::fs::File::create(&filename).await?;
-    let mut buf_writer = tokio::io::BufWriter::new(writer);
+    let data_item_stream = bundle::ans104_bundle_data_item_stream_with_options(
+        tokio::io::BufReader::with_capacity(args.read_buffer, data_reader),
+        bundle::BundleStreamOptions {
+            verify_ids: args.verify_ids,
+            expected_size,
+            hash_data: args.hash_data,
+            skip_invalid_items: args.skip_invalid_items,
+            max_item_size: args.max_item_size,
+            warn_duplicate_tags: args.warn_duplicate_tags,
+            strict: args.strict,
+            mem_budget: args.shared_mem_budget.clone(),
+            spill: args.spill_threshold.map(|threshold| bundle::SpillOptions {
+                threshold,
+                file_prefix: output_prefix.clone(),
+            }),
+        },
+    );
+    let mut data_item_stream: LocalBoxStream<'_, anyhow::Result<DataItem>> =
+        data_item_stream.boxed_local();
 
-    let mut json_writer = async_json::ArrayWriter::new(&mut buf_writer);
-    json_writer.write_open_bracket().await?;
+    let output_options = OutputOptions {
+        inline_json_max_size: args.inline_json.then_some(args.inline_json_max_size),
+        text_anchor: args.text_anchor,
+        only_tags_prefix: args.only_tags_prefix.clone(),
+        flatten_tags_prefix: args.flatten_tags_prefix.clone(),
+        with_offsets: args.with_offsets,
+        rename_fields: args.rename_fields.clone(),
+        data_preview: args.data_preview,
+    };
+    // separate handle of output file for fsync
+    let mut sync_file = None;
+    let mut partial_output = None;
+    let mut sink: Box<dyn OutputSink> = if args.split {
+        Box::new(SplitSink::new(output_prefix.clone(), output_options))
+    } else if let Some(url) = args.s3_output() {
+        s3_sink(url, output_options, tx, args).await?
+    } else {
+        let writer: Box<dyn AsyncWrite + Unpin> = if args.stdout {
+            Box::new(tokio::io::stdout())
+        } else {
+            let output = PartialOutput::new(&filename, args.remove_partial);
+            let file = tokio::fs::File::create(&output.tmp_path).await?;
+            partial_output = Some(output);
+            sync_file = Some(file.try_clone().await?);
+            Box::new(file)
+        };
+        let writer = tokio::io::BufWriter::with_capacity(args.write_buffer, writer);
+        match args.format {
+            _ if args.tar.is_some() => Box::new(TarSink::new(writer)),
+            OutputFormat::Json => Box::new(json_array_sink(writer, output_options, tx, args)?),
+            #[cfg(feature = "csv")]
+            OutputFormat::Csv => Box::new(async_csv::CsvWriter::new(writer, args.csv_tag.clone())),
+        }
+    };
+    sink.open().await?;
 
-    println!("Parsing data");
-    while let Some(data_item) = data_item_stream.try_next().await? {
-        json_writer.write_item(&data_item).await?;
-        println!(
+    let mut tags_writer = match &args.tags_file {
+        Some(tags_file) => Some(async_json::NdJsonWriter::new(
+            tokio::io::BufWriter::with_capacity(
+                args.write_buffer,
+                tokio::fs::File::create(tags_file).await?,
+            ),
+        )),
+        None => None,
+    };
+    let mut triples_writer = match &args.tags_triples {
+        Some(triples_file) => Some(async_json::NdJsonWriter::new(
+            tokio::io::BufWriter::with_capacity(
+                args.write_buffer,
+                tokio::fs::File::create(triples_file).await?,
+            ),
+        )),
+        None => None,
+    };
+
+    let mut stats = Stats::with_group_tag(&args.stats_group_tag);
+    // DataItems whose signatures are not verified, noted once
+    let mut unverified = HashSet::new();
+    // DataItems with invalid signatures found in --verify-mode report
+    let mut invalid_signatures = vec![];
+    // signature type of first DataItem for --uniform-sig, including DataItems filtered out
+    let mut first_signature_name = None;
+
+    info!(args, "Parsing data");
+    let cancelled = cancelled(args);
+    tokio::pin!(cancelled);
+    // DataItems collected for --reverse before cancellation are still written, in reverse
+    let mut cancellation = None;
+    if args.reverse {
+        let mut data_items = vec![];
+        loop {
+            tokio::select! {
+                data_item = data_item_stream.try_next() => match data_item.context(Error::Parse)? {
+                    Some(data_item) => data_items.push(data_item),
+                    None => break,
+                },
+                err = &mut cancelled => {
+                    cancellation = Some(err);
+                    break;
+                }
+            }
+        }
+        data_items.reverse();
+        data_item_stream = futures_util::stream::iter(data_items.into_iter().map(Ok)).boxed_local();
+    }
+    loop {
+        let data_item = tokio::select! {
+            data_item = data_item_stream.try_next() => data_item.context(Error::Parse)?,
+            err = &mut cancelled, if cancellation.is_none() => {
+                cancellation = Some(err);
+                break;
+            }
+        };
+        let Some(data_item) = data_item else {
+            break;
+        };
+        if args.uniform_sig {
+            match &first_signature_name {
+                Some(expected) if *expected != data_item.signature_name => {
+                    return Err(anyhow::anyhow!(
+                        "[{}] signature type {} differs from {expected} used by previous DataItems",
+                        data_item.bundle_id,
+                        data_item.signature_name
+                    ));
+                }
+                Some(_) => {}
+                None => first_signature_name = Some(data_item.signature_name.clone()),
+            }
+        }
+        if args.verify {
+            match data_item
+                .verify_ed25519()
+                .or_else(|| data_item.verify_rsa())
+            {
+                Some(true) => {}
+                Some(false) if args.verify_mode == VerifyMode::Report => {
+                    eprintln!(
+                        "WARNING: [{}] {} signature is invalid",
+                        data_item.bundle_id, data_item.signature_name
+                    );
+                    invalid_signatures.push(data_item.bundle_id.to_string());
+                }
+                Some(false) => {
+                    return Err(anyhow::anyhow!(
+                        "[{}] {} signature is invalid",
+                        data_item.bundle_id,
+                        data_item.signature_name
+                    ))
+                }
+                None => {
+                    let skipped = match data_item.data_file {
+                        Some(_) => "spilled DataItems".to_string(),
+                        None => format!("{} DataItems", data_item.signature_name),
+                    };
+                    if unverified.insert(skipped.clone()) {
+                        info!(args, "NOTE: skipping signature verification of {skipped}");
+                    }
+                }
+            }
+        }
+        let selected = args.has_tag.iter().all(|name| data_item.has_tag(name))
+            && args
+                .only_signature_type
+                .is_none_or(|scheme| scheme.name() == data_item.signature_name)
+            && (args.content_type.is_empty() || data_item.content_type_matches(&args.content_type));
+        if !selected {
+            if let Some(data_file) = &data_item.data_file {
+                tokio::fs::remove_file(&data_file.path).await?;
+            }
+            continue;
+        }
+        sink.write_item(&data_item).await?;
+        if let Some(tags_writer) = &mut tags_writer {
+            tags_writer
+                .write_item(&TagsRecord::from(&data_item))
+                .await?;
+        }
+        if let Some(triples_writer) = &mut triples_writer {
+            for triple in TagTriple::all(&data_item) {
+                triples_writer.write_item(&triple).await?;
+            }
+        }
+        stats.update(&data_item);
+        metrics::ITEMS_WRITTEN.inc();
+        if args
+            .fsync_interval
+            .is_some_and(|interval| stats.total_items.is_multiple_of(interval))
+        {
+            sink.flush().await?;
+            if let Some(sync_file) = &sync_file {
+                sync_file.sync_data().await?;
+            }
+        }
+        info!(
+            args,
             "[{}] tags count: {} data size: {}",
             data_item.bundle_id,
             data_item.tags.len(),
-            data_item.data.0.len(),
+            data_item.data_len(),
         )
     }
-    println!();
-    println!("Done!");
+    sink.finish().await?;
+    if let Some(tags_writer) = &mut tags_writer {
+        tags_writer.get_mut().flush().await?;
+    }
+    if let Some(triples_writer) = &mut triples_writer {
+        triples_writer.get_mut().flush().await?;
+    }
+    if let Some(err) = cancellation {
+        info!(
+            args,
+            "Output closed after {} DataItems, it is incomplete", stats.total_items
+        );
+        return Err(err.into());
+    }
+    if let Some(partial_output) = partial_output {
+        if let Some(sync_file) = &sync_file {
+            sync_file.sync_data().await?;
+        }
+        partial_output.complete().await?;
+    }
 
-    json_writer.write_close_bracket().await?;
-    buf_writer.flush().await?;
-    println!("Bundle data stored in: {filename}");
+    info!(args);
+    info!(args, "Done!");
+    if args.split {
+        info!(args, "Bundle data stored in: {output_prefix}.<index>.json");
+    } else if !args.stdout {
+        info!(args, "Bundle data stored in: {filename}");
+    }
+    if let Some(tags_file) = &args.tags_file {
+        info!(args, "Tags stored in: {tags_file}");
+    }
+    if let Some(triples_file) = &args.tags_triples {
+        info!(args, "Tag triples stored in: {triples_file}");
+    }
+
+    info!(args);
+    info!(args, "{}", stats.to_string().trim_end());
+    if let Some(stats_file) = &args.stats_file {
+        tokio::fs::write(stats_file, serde_json::to_vec_pretty(&stats)?).await?;
+        info!(args, "Stats stored in: {stats_file}");
+    }
+    metrics::BUNDLES_DUMPED.inc();
+    if !invalid_signatures.is_empty() {
+        return Err(anyhow::anyhow!(
+            "{} DataItems have invalid signature: {}",
+            invalid_signatures.len(),
+            invalid_signatures.join(", ")
+        ));
+    }
     Ok(())
 }
+
+// resolves once --deadline passes or Ctrl-C is pressed
+async fn cancelled(args: &Args) -> Error {
+    let deadline = async {
+        match args.deadline_at {
+            Some(deadline_at) => tokio::time::sleep_until(deadline_at).await,
+            None => std::future::pending().await,
+        }
+    };
+    tokio::select! {
+        _ = deadline => Error::DeadlineExceeded {
+            deadline: args.deadline.map(Into::into).unwrap_or_default(),
+        },
+        _ = args.interrupted.cancelled() => Error::Interrupted,
+    }
+}
+
+// for phases which have no output to close yet, they are just abandoned
+async fn unless_cancelled<T>(
+    args: &Args,
+    future: impl std::future::Future<Output = anyhow::Result<T>>,
+) -> anyhow::Result<T> {
+    tokio::select! {
+        res = future => res,
+        err = cancelled(args) => Err(err.into()),
+    }
+}
+
+async fn sleep_unless_cancelled(duration: std::time::Duration, args: &Args) -> anyhow::Result<()> {
+    tokio::select! {
+        _ = tokio::time::sleep(duration) => Ok(()),
+        err = cancelled(args) => Err(err.into()),
+    }
+}
+
+// Polls newest bundle transactions matching the query and dumps the ones not seen before.
+// First poll only records currently existing transactions so that just new ones get dumped.
+async fn follow(arweave_client: &arweave::Client, args: &Args) -> anyhow::Result<()> {
+    let mut tags = vec![TagFilter::bundles()];
+    for query in &args.query {
+        let (name, value) = query
+            .split_once('=')
+            .ok_or_else(|| anyhow::anyhow!("Query must be in NAME=VALUE format: {query}"))?;
+        tags.push(TagFilter {
+            name: name.to_string(),
+            values: vec![value.to_string()],
+        });
+    }
+    let query = TransactionQuery {
+        owners: args.owner.clone(),
+        tags,
+        sort: Some(SortOrder::HeightDesc),
+        ..Default::default()
+    };
+
+    let mut seen = SeenIds::new(FOLLOW_SEEN_LIMIT);
+    // with number of polls they were retried on
+    let mut failed: Vec<(Base64, usize)> = vec![];
+    let mut error_reports = VecDeque::new();
+    let mut first_poll = true;
+    loop {
+        let poll = tokio::select! {
+            res = poll_new_ids(arweave_client, &query, &seen, args.prefix_scan_pages) => res,
+            err = cancelled(args) => return Err(err.into()),
+        };
+        let new_ids = match poll {
+            Ok(new_ids) => new_ids,
+            Err(err) => {
+                eprintln!("Failed to poll for new bundles: {err:#}");
+                sleep_unless_cancelled(*args.interval, args).await?;
+                continue;
+            }
+        };
+
+        if first_poll {
+            info!(args, "Following {} existing bundles", new_ids.len());
+            for id in new_ids.into_iter().rev() {
+                seen.insert(id.0);
+            }
+            first_poll = false;
+        } else {
+            // previously failed ones first, then new ones oldest first
+            let ids = std::mem::take(&mut failed)
+                .into_iter()
+                .chain(new_ids.into_iter().rev().map(|id| (id, 0)));
+            for (id, retries) in ids {
+                seen.insert(id.0.clone());
+                let Err(err) = dump_transaction(arweave_client, &id, args).await else {
+                    continue;
+                };
+                if retries < FOLLOW_RETRIES {
+                    eprintln!("Failed to dump {id}, will retry on next poll: {err:#}");
+                    failed.push((id, retries + 1));
+                    continue;
+                }
+                eprintln!("Failed to dump {id}, giving up after {retries} retries: {err:#}");
+                if error_reports.len() == FOLLOW_ERRORS_LIMIT {
+                    error_reports.pop_front();
+                }
+                error_reports.push_back(ErrorReport::new(&err, Some(id.to_string())));
+                tokio::fs::write(
+                    &args.errors_file,
+                    serde_json::to_vec_pretty(&error_reports)?,
+                )
+                .await?;
+                info!(args, "Errors stored in: {}", args.errors_file);
+            }
+        }
+
+        sleep_unless_cancelled(*args.interval, args).await?;
+    }
+}
+
+/// Most recently seen transaction ids, the oldest one is forgotten once there are `limit` of them.
+struct SeenIds {
+    ids: HashSet<Vec<u8>>,
+    order: VecDeque<Vec<u8>>,
+    limit: usize,
+}
+
+impl SeenIds {
+    fn new(limit: usize) -> Self {
+        Self {
+            ids: HashSet::new(),
+            order: VecDeque::new(),
+            limit,
+        }
+    }
+
+    fn insert(&mut self, id: Vec<u8>) {
+        if !self.ids.insert(id.clone()) {
+            return;
+        }
+        self.order.push_back(id);
+        if self.order.len() > self.limit {
+            if let Some(oldest) = self.order.pop_front() {
+                self.ids.remove(&oldest);
+            }
+        }
+    }
+
+    fn contains(&self, id: &[u8]) -> bool {
+        self.ids.contains(id)
+    }
+}
+
+// newest first, up to the first already seen transaction
+async fn poll_new_ids(
+    arweave_client: &arweave::Client,
+    query: &TransactionQuery,
+    seen: &SeenIds,
+    max_pages: usize,
+) -> anyhow::Result<Vec<Base64>> {
+    let mut new_ids = vec![];
+    let mut query = query.clone();
+    for _ in 0..max_pages {
+        let page = arweave_client.query_transactions(&query).await?;
+        for edge in &page.edges {
+            if seen.contains(&edge.node.id.0) {
+                return Ok(new_ids);
+            }
+            new_ids.push(edge.node.id.clone());
+        }
+        if !page.page_info.has_next_page {
+            break;
+        }
+        query.after = page.last_cursor().map(str::to_string);
+    }
+    Ok(new_ids)
+}
+
+async fn resolve_prefix(
+    arweave_client: &arweave::Client,
+    prefix: &str,
+    owners: Vec<String>,
+    max_pages: usize,
+) -> anyhow::Result<Base64> {
+    let mut matches = arweave_client
+        .find_bundles_by_id_prefix(prefix, owners, max_pages)
+        .await?;
+
+    match matches.len() {
+        0 => Err(anyhow::anyhow!(
+            "No bundle transaction found with ID prefix: {prefix}"
+        )),
+        1 => Ok(matches.remove(0)),
+        _ => Err(anyhow::anyhow!(
+            "ID prefix {prefix} is ambiguous, candidates:\n{}",
+            matches
+                .iter()
+                .map(Base64::to_string)
+                .collect::<Vec<_>>()
+                .join("\n")
+        )),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use wiremock::{
+        matchers::{method, path},
+        Mock, MockServer, ResponseTemplate,
+    };
+
+    use super::*;
+
+    const BUNDLE_ID: &str = "uYpAeGCj8Xe_J0sKiZ_aJ4Zl1zQLgDH5ia-pqtNLJEA";
+
+    // serves bundle transaction metadata, but never answers offset request
+    async fn stuck_gateway() -> MockServer {
+        let tag = |name: &str, value: &str| {
+            serde_json::json!({
+                "name": Base64(name.as_bytes().to_vec()).to_string(),
+                "value": Base64(value.as_bytes().to_vec()).to_string(),
+            })
+        };
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path(format!("/tx/{BUNDLE_ID}")))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "format": 2,
+                "id": BUNDLE_ID,
+                "last_tx": "",
+                "owner": "",
+                "tags": [tag("Bundle-Format", "binary"), tag("Bundle-Version", "2.0.0")],
+                "target": "",
+                "quantity": "0",
+                "data_root": "",
+                "data": "",
+                "data_size": "1000",
+                "reward": "0",
+                "signature": "",
+            })))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path(format!("/tx/{BUNDLE_ID}/offset")))
+            .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_secs(3600)))
+            .mount(&mock_server)
+            .await;
+        mock_server
+    }
+
+    fn args(mock_server: &MockServer, options: &[&str]) -> Args {
+        let mut args = Args::try_parse_from(
+            [
+                "main",
+                "-q",
+                "-t",
+                BUNDLE_ID,
+                "--base-url",
+                &mock_server.uri(),
+            ]
+            .iter()
+            .chain(options),
+        )
+        .expect("should parse");
+        args.deadline_at = args
+            .deadline
+            .map(|deadline| tokio::time::Instant::now() + *deadline);
+        args
+    }
+
+    async fn dump_from_stuck_gateway(args: &Args) -> anyhow::Error {
+        let client = arweave::Client::new(&args.base_url).expect("should not fail");
+        let id = BUNDLE_ID.parse().expect("should parse");
+        tokio::time::timeout(
+            Duration::from_secs(10),
+            dump_transaction(&client, &id, args),
+        )
+        .await
+        .expect("should not hang")
+        .expect_err("should fail")
+    }
+
+    #[tokio::test]
+    async fn test_deadline_cancels_stuck_metadata_fetch() {
+        let mock_server = stuck_gateway().await;
+        let args = args(&mock_server, &["--strict", "--deadline", "200ms"]);
+        let err = dump_from_stuck_gateway(&args).await;
+        assert!(
+            matches!(err.downcast_ref(), Some(Error::DeadlineExceeded { .. })),
+            "{err:#}"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_interruption_cancels_stuck_count() {
+        let mock_server = stuck_gateway().await;
+        let args = args(&mock_server, &["--count"]);
+        let interrupted = args.interrupted.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(200)).await;
+            interrupted.cancel();
+        });
+        let err = dump_from_stuck_gateway(&args).await;
+        assert!(
+            matches!(err.downcast_ref(), Some(Error::Interrupted)),
+            "{err:#}"
+        );
+    }
+
+    #[test]
+    fn test_tags_files_are_rejected_for_multiple_transactions() {
+        for option in ["--tags-file", "--tags-triples"] {
+            let args = Args::try_parse_from([
+                "main",
+                "-t",
+                BUNDLE_ID,
+                "-t",
+                BUNDLE_ID,
+                option,
+                "tags.ndjson",
+            ])
+            .expect("should parse");
+            let err = check_multiple_transactions_options(&args).expect_err("should fail");
+            assert!(err.to_string().contains(option), "{err:#}");
+
+            Args::try_parse_from(["main", "--follow", option, "tags.ndjson"])
+                .expect_err("should conflict with --follow");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_uniform_sig_checks_filtered_out_data_items() {
+        let data = hex::decode(include_str!("../../res/mixed_signature_bundle.hex"))
+            .expect("should parse");
+        let output_file = std::env::temp_dir().join(format!(
+            "arweave-dumper-uniform-sig-{}.json",
+            std::process::id()
+        ));
+        let output_file = output_file.to_str().expect("should be utf-8");
+        let dump = |options: &'static [&'static str]| {
+            let args = Args::try_parse_from(
+                ["main", "-q", "--input-file", "bundle", "-o", output_file]
+                    .iter()
+                    .chain(options),
+            )
+            .expect("should parse");
+            let data = data.clone();
+            async move {
+                dump_reader(
+                    Box::new(std::io::Cursor::new(data)),
+                    None,
+                    "bundle".to_string(),
+                    None,
+                    &args,
+                )
+                .await
+            }
+        };
+
+        dump(&["--only-signature-type", "ed25519"])
+            .await
+            .expect("should dump");
+        let err = dump(&[
+            "--only-signature-type",
+            "ed25519",
+            "--uniform-sig",
+            "--remove-partial",
+        ])
+        .await
+        .expect_err("should fail");
+        assert!(err.to_string().contains("differs from arweave"), "{err:#}");
+        tokio::fs::remove_file(output_file)
+            .await
+            .expect("should remove");
+    }
+
+    #[tokio::test]
+    async fn test_errors_file_is_written_only_for_failures_of_dump() {
+        let mock_server = MockServer::start().await;
+        let ids = [BUNDLE_ID, "uYpAeGCj8Xe_J0sKiZ_aJ4Zl1zQLgDH5ia-pqtNLJEE"];
+        for id in ids {
+            Mock::given(method("GET"))
+                .and(path(format!("/tx/{id}")))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                    "format": 2,
+                    "id": id,
+                    "last_tx": "",
+                    "owner": "",
+                    "tags": [],
+                    "target": "",
+                    "quantity": "0",
+                    "data_root": "",
+                    "data": "",
+                    "data_size": "0",
+                    "reward": "0",
+                    "signature": "",
+                })))
+                .mount(&mock_server)
+                .await;
+        }
+        let errors_file =
+            std::env::temp_dir().join(format!("arweave-dumper-errors-{}.json", std::process::id()));
+        let errors_file = errors_file.to_str().expect("should be utf-8");
+        let client = arweave::Client::new(&mock_server.uri()).expect("should not fail");
+        let transaction_ids = ids.map(|id| id.parse().expect("should parse"));
+
+        let count_args = args(&mock_server, &["--count", "--errors-file", errors_file]);
+        dump_transactions(&client, &transaction_ids, &count_args)
+            .await
+            .expect_err("should fail");
+        assert!(!std::path::Path::new(errors_file).exists());
+
+        let args = args(&mock_server, &["--errors-file", errors_file]);
+        dump_transactions(&client, &transaction_ids, &args)
+            .await
+            .expect_err("should fail");
+        let error_reports: Vec<serde_json::Value> = serde_json::from_slice(
+            &tokio::fs::read(errors_file)
+                .await
+                .expect("should be written"),
+        )
+        .expect("should parse");
+        assert_eq!(error_reports.len(), 2);
+        tokio::fs::remove_file(errors_file)
+            .await
+            .expect("should remove");
+    }
+
+    #[test]
+    fn test_seen_ids_forget_oldest() {
+        let mut seen = SeenIds::new(2);
+        seen.insert(vec![1]);
+        seen.insert(vec![2]);
+        seen.insert(vec![1]);
+        assert!(seen.contains(&[1]) && seen.contains(&[2]));
+
+        seen.insert(vec![3]);
+        assert!(!seen.contains(&[1]));
+        assert!(seen.contains(&[2]) && seen.contains(&[3]));
+        assert_eq!(seen.order.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_interrupted_reverse_writes_collected_data_items() {
+        let hex_str =
+            include_str!("../../res/uYpAeGCj8Xe_J0sKiZ_aJ4Zl1zQLgDH5ia-pqtNLJEA_data.hex");
+        let data = hex::decode(hex_str).expect("should parse");
+        let expected: Vec<DataItem> = bundle::ans104_bundle_data_item_stream(data.as_slice())
+            .try_collect()
+            .await
+            .expect("should parse");
+        // the last DataItem never arrives
+        let (_sender, stalled) = tokio::io::duplex(1);
+        let reader =
+            tokio::io::AsyncReadExt::chain(Cursor::new(data[..data.len() - 1].to_vec()), stalled);
+
+        let output_file = std::env::temp_dir().join(format!(
+            "arweave-dumper-reverse-{}.json",
+            std::process::id()
+        ));
+        let output_file = output_file.to_str().expect("should be utf-8");
+        let args = Args::try_parse_from([
+            "main",
+            "-q",
+            "--input-file",
+            "bundle",
+            "--reverse",
+            "-o",
+            output_file,
+        ])
+        .expect("should parse");
+        let interrupted = args.interrupted.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(200)).await;
+            interrupted.cancel();
+        });
+        let err = dump_reader(Box::new(reader), None, "bundle".to_string(), None, &args)
+            .await
+            .expect_err("should be interrupted");
+        assert!(
+            matches!(err.downcast_ref(), Some(Error::Interrupted)),
+            "{err:#}"
+        );
+
+        let tmp_path = format!("{output_file}.tmp");
+        let written: Vec<DataItem> =
+            serde_json::from_slice(&tokio::fs::read(&tmp_path).await.expect("should be written"))
+                .expect("should be closed");
+        let ids: Vec<_> = written.iter().map(|item| &item.bundle_id).collect();
+        let expected_ids: Vec<_> = expected[..3]
+            .iter()
+            .rev()
+            .map(|item| &item.bundle_id)
+            .collect();
+        assert_eq!(ids, expected_ids);
+        tokio::fs::remove_file(tmp_path)
+            .await
+            .expect("should remove");
+    }
+
+    #[test]
+    fn test_reverse_conflicts_with_mem_budget() {
+        Args::try_parse_from(["main", "-t", BUNDLE_ID, "--reverse", "--mem-budget", "1024"])
+            .expect_err("should conflict with --mem-budget");
+    }
+}
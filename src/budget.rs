@@ -0,0 +1,78 @@
+use std::sync::Arc;
+
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+// budget is tracked in KiB so that semaphore permit count fits into u32
+const UNIT: usize = 1024;
+
+/// Upper bound of DataItem data bytes buffered in memory at once, shared by all clones.
+#[derive(Debug, Clone)]
+pub struct MemoryBudget {
+    semaphore: Arc<Semaphore>,
+    units: u32,
+}
+
+/// Part of the budget held until dropped.
+#[derive(Debug)]
+pub struct BudgetPermit {
+    _permit: OwnedSemaphorePermit,
+}
+
+impl MemoryBudget {
+    pub fn new(bytes: usize) -> anyhow::Result<Self> {
+        let units = u32::try_from(bytes.div_ceil(UNIT))
+            .map_err(|_| anyhow::anyhow!("Memory budget of {bytes} bytes is too large"))?;
+        Ok(Self {
+            semaphore: Arc::new(Semaphore::new(units as usize)),
+            units,
+        })
+    }
+
+    /// Waits until `bytes` are available. Fails right away if they exceed the whole budget.
+    pub async fn acquire(&self, bytes: usize) -> anyhow::Result<BudgetPermit> {
+        let units = bytes.div_ceil(UNIT);
+        if units > self.units as usize {
+            return Err(anyhow::anyhow!(
+                "{bytes} bytes exceed whole memory budget of {} bytes",
+                self.units as usize * UNIT
+            ));
+        }
+        let permit = self
+            .semaphore
+            .clone()
+            .acquire_many_owned(units as u32)
+            .await?;
+        Ok(BudgetPermit { _permit: permit })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_acquire_waits_for_release() {
+        let budget = MemoryBudget::new(10 * UNIT).expect("should create");
+
+        let permit = budget.acquire(6 * UNIT).await.expect("should acquire");
+        let waiting = tokio::time::timeout(Duration::from_millis(50), budget.acquire(6 * UNIT));
+        assert!(waiting.await.is_err(), "should wait for release");
+
+        drop(permit);
+        tokio::time::timeout(Duration::from_millis(50), budget.acquire(6 * UNIT))
+            .await
+            .expect("should not wait")
+            .expect("should acquire");
+    }
+
+    #[tokio::test]
+    async fn test_acquire_more_than_budget_fails() {
+        let budget = MemoryBudget::new(10 * UNIT).expect("should create");
+        budget
+            .acquire(10 * UNIT + 1)
+            .await
+            .expect_err("should fail");
+    }
+}
@@ -1,15 +1,33 @@
+use std::{
+    pin::Pin,
+    task::{Context as TaskContext, Poll},
+};
+
 use anyhow::Context;
-use arweave_rs::crypto::{base64::Base64, hash::sha256};
+use arweave_rs::crypto::{
+    base64::Base64,
+    hash::{deep_hash, sha256, DeepHashItem},
+};
 use async_stream::try_stream;
 use futures_core::Stream;
 use serde::{Deserialize, Serialize};
-use tokio::io::{AsyncRead, AsyncReadExt};
+use sha3::Digest;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWriteExt, ReadBuf};
 
-use crate::avro::{self, BundleTag};
+use crate::{
+    avro::{self, BundleTag},
+    budget::MemoryBudget,
+    ed25519,
+    signature::{self, SignatureHeader, SignatureScheme},
+};
 
-#[derive(Debug, Serialize, Deserialize)]
+/// `Debug` output shows only length of data, not data itself.
+#[derive(Serialize, Deserialize)]
 pub struct DataItem {
     pub signature_name: String,
+    /// ANS-104 signature type as read, missing in JSON written before it was added
+    #[serde(default)]
+    pub signature_type: u16,
     pub signature: Base64,
     pub bundle_id: Base64,
     pub owner_public_key: Base64,
@@ -17,130 +35,682 @@ pub struct DataItem {
     pub anchor: Option<Base64>,
     pub tags: Vec<BundleTag>,
     pub data: Base64,
+    /// set when data was spilled into a file instead of `data`, see [`SpillOptions`]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data_file: Option<DataFile>,
+    /// hex encoded SHA-256 of data, computed while data is read, see [`BundleStreamOptions::hash_data`]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data_sha256: Option<String>,
+    /// Avro encoded tags as read, signature covers them rather than decoded `tags`
+    #[serde(skip)]
+    pub raw_tags: Vec<u8>,
+    /// where DataItem is in its bundle, set by bundle stream
+    #[serde(skip)]
+    pub position: Option<ItemPosition>,
+}
+
+impl std::fmt::Debug for DataItem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DataItem")
+            .field("signature_name", &self.signature_name)
+            .field("signature_type", &self.signature_type)
+            .field("signature", &self.signature)
+            .field("bundle_id", &self.bundle_id)
+            .field("owner_public_key", &self.owner_public_key)
+            .field("target", &self.target)
+            .field("anchor", &self.anchor)
+            .field("tags", &self.tags)
+            .field("data", &format_args!("<{} bytes>", self.data.0.len()))
+            .field("data_file", &self.data_file)
+            .field("data_sha256", &self.data_sha256)
+            .field("raw_tags", &format_args!("<{} bytes>", self.raw_tags.len()))
+            .field("position", &self.position)
+            .finish()
+    }
+}
+
+/// MIME type like `image/png`, or with trailing wildcard like `image/*` or `*`, compared case insensitively.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContentTypePattern {
+    // lowercase, without the wildcard
+    prefix: String,
+    wildcard: bool,
+}
+
+impl ContentTypePattern {
+    pub fn new(pattern: &str) -> anyhow::Result<Self> {
+        let pattern = pattern.trim().to_ascii_lowercase();
+        let (prefix, wildcard) = match pattern.strip_suffix('*') {
+            Some(prefix) => (prefix.to_string(), true),
+            None => (pattern.clone(), false),
+        };
+        if prefix.contains('*') || (!wildcard && prefix.is_empty()) {
+            return Err(anyhow::anyhow!(
+                "invalid content type pattern '{pattern}', wildcard is allowed only at the end"
+            ));
+        }
+        Ok(Self { prefix, wildcard })
+    }
+
+    /// MIME parameters like `; charset=utf-8` of content type are ignored.
+    pub fn matches(&self, content_type: &str) -> bool {
+        let mime = content_type
+            .split(';')
+            .next()
+            .unwrap_or_default()
+            .trim()
+            .to_ascii_lowercase();
+        match self.wildcard {
+            true => mime.starts_with(&self.prefix),
+            false => mime == self.prefix,
+        }
+    }
+}
+
+/// Index of DataItem in bundle table, with offset and length of its bytes in bundle data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct ItemPosition {
+    pub index: usize,
+    pub offset: u64,
+    pub length: u64,
+}
+
+/// File holding data of a DataItem.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DataFile {
+    pub path: String,
+    pub size: usize,
+}
+
+impl DataItem {
+    pub fn get_tag(&self, name: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|tag| tag.name == name)
+            .map(|tag| tag.value.as_str())
+    }
+
+    /// Numeric signature type, for DataItems deserialized without it taken from `signature_name`.
+    pub fn signature_type_id(&self) -> Option<u16> {
+        match self.signature_type {
+            0 => SignatureScheme::from_name(&self.signature_name)
+                .map(SignatureScheme::signature_type),
+            signature_type => Some(signature_type),
+        }
+    }
+
+    pub fn has_tag(&self, name: &str) -> bool {
+        self.tags.iter().any(|tag| tag.name == name)
+    }
+
+    /// Whether `Content-Type` tag matches any of patterns, DataItem without the tag matches none.
+    pub fn content_type_matches(&self, patterns: &[ContentTypePattern]) -> bool {
+        self.get_tag("Content-Type").is_some_and(|content_type| {
+            patterns.iter().any(|pattern| pattern.matches(content_type))
+        })
+    }
+
+    /// Tag names which appear more than once, each reported once in order of its first repetition.
+    /// Allowed by the spec, but often a sign of buggy uploader.
+    pub fn duplicate_tag_names(&self) -> Vec<&str> {
+        let mut seen = std::collections::HashSet::new();
+        let mut duplicates = vec![];
+        for tag in &self.tags {
+            if !seen.insert(tag.name.as_str()) && !duplicates.contains(&tag.name.as_str()) {
+                duplicates.push(tag.name.as_str());
+            }
+        }
+        duplicates
+    }
+
+    /// Length of DataItem data, including data spilled into a file.
+    pub fn data_len(&self) -> usize {
+        self.data_file
+            .as_ref()
+            .map_or(self.data.0.len(), |data_file| data_file.size)
+    }
+
+    /// Recomputes the DataItem id from its signature and compares it with `bundle_id`.
+    pub fn verify_id(&self) -> bool {
+        SignatureScheme::from_name(&self.signature_name)
+            .is_some_and(|scheme| scheme.derive_id(&self.signature) == self.bundle_id)
+    }
+
+    /// Deep hash of DataItem fields signed by its owner. Not available when data was spilled
+    /// or raw tags are unknown, e.g. for DataItems read back from JSON output.
+    pub fn signature_message(&self) -> Option<[u8; 48]> {
+        let scheme = SignatureScheme::from_name(&self.signature_name)?;
+        if self.data_file.is_some() || (self.raw_tags.is_empty() && !self.tags.is_empty()) {
+            return None;
+        }
+        let optional_field = |field: &Option<Base64>| {
+            DeepHashItem::Blob(field.as_ref().map_or(vec![], |field| field.0.clone()))
+        };
+        Some(deep_hash(DeepHashItem::from_children(vec![
+            DeepHashItem::from_item(b"dataitem"),
+            DeepHashItem::from_item(b"1"),
+            DeepHashItem::from_item(scheme.signature_type().to_string().as_bytes()),
+            DeepHashItem::from_item(&self.owner_public_key.0),
+            optional_field(&self.target),
+            optional_field(&self.anchor),
+            DeepHashItem::from_item(&self.raw_tags),
+            DeepHashItem::from_item(&self.data.0),
+        ])))
+    }
+
+    /// Verifies signature of ed25519 and solana DataItems. `None` for other signature types
+    /// or when [`DataItem::signature_message`] is not available.
+    pub fn verify_ed25519(&self) -> Option<bool> {
+        match SignatureScheme::from_name(&self.signature_name)? {
+            SignatureScheme::Ed25519 | SignatureScheme::Solana => {}
+            SignatureScheme::Arweave | SignatureScheme::Ethereum => return None,
+        }
+        let message = self.signature_message()?;
+        Some(ed25519::verify(
+            &self.owner_public_key.0,
+            &message,
+            &self.signature.0,
+        ))
+    }
+
+    /// Verifies RSA-PSS signature of arweave DataItems, owner public key is the RSA modulus.
+    /// `None` for other signature types or when [`DataItem::signature_message`] is not available.
+    pub fn verify_rsa(&self) -> Option<bool> {
+        if SignatureScheme::from_name(&self.signature_name)? != SignatureScheme::Arweave {
+            return None;
+        }
+        let message = self.signature_message()?;
+        Some(signature::verify_rsa_pss(
+            &self.owner_public_key.0,
+            &message,
+            &self.signature.0,
+        ))
+    }
+
+    /// Anchor as text if it is valid UTF-8 without control characters, trailing zero padding is dropped.
+    pub fn anchor_string(&self) -> Option<String> {
+        let anchor = self.anchor.as_ref()?;
+        let text = std::str::from_utf8(&anchor.0).ok()?.trim_end_matches('\0');
+        if text.is_empty() || text.chars().any(char::is_control) {
+            return None;
+        }
+        Some(text.to_string())
+    }
+
+    /// Derives owner address from the owner public key according to the signature type.
+    pub fn owner_address(&self) -> Option<String> {
+        let owner = &self.owner_public_key.0;
+        match self.signature_name.as_str() {
+            "arweave" => Some(Base64::from(&sha256(owner)[..]).to_string()),
+            // uncompressed secp256k1 key: 0x04 prefix followed by 64 bytes of coordinates
+            "ethereum" if owner.len() == 65 && owner[0] == 0x04 => {
+                let hash = sha3::Keccak256::digest(&owner[1..]);
+                Some(format!("0x{}", hex::encode(&hash[12..])))
+            }
+            "solana" => Some(bs58::encode(owner).into_string()),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct BundleStreamOptions {
+    /// fail the stream if any DataItem id does not match its signature or the bundle table entry
+    pub verify_ids: bool,
+    /// declared transaction data size, stream fails early if bundle header and table account for different size
+    pub expected_size: Option<usize>,
+    /// write data of big DataItems into files instead of keeping it in memory
+    pub spill: Option<SpillOptions>,
+    /// in-memory data of every DataItem takes its size from the budget,
+    /// given back when next DataItem is requested from the stream (or the stream is dropped)
+    pub mem_budget: Option<MemoryBudget>,
+    /// compute SHA-256 of every DataItem data into `data_sha256`
+    pub hash_data: bool,
+    /// malformed DataItem is skipped with a warning instead of failing the stream,
+    /// read errors of underlying reader still fail it
+    pub skip_invalid_items: bool,
+    /// stream fails before reading any DataItem if bundle table declares one bigger than this
+    pub max_item_size: Option<usize>,
+    /// report DataItems with repeated tag names, see [`DataItem::duplicate_tag_names`]
+    pub warn_duplicate_tags: bool,
+    /// every [`ParseWarning`] fails the stream instead of being printed on stderr,
+    /// duplicate tags are checked even without `warn_duplicate_tags`
+    pub strict: bool,
 }
 
-pub async fn read_data_item<R>(mut reader: R) -> anyhow::Result<DataItem>
+/// Non-fatal oddity of bundle data, printed on stderr unless [`BundleStreamOptions::strict`] makes it an error.
+#[derive(Debug, thiserror::Error)]
+pub enum ParseWarning {
+    #[error("bundle is empty, it has no DataItems")]
+    EmptyBundle,
+    #[error("skipping invalid DataItem {entry_id}: {reason}")]
+    InvalidItemSkipped { entry_id: Base64, reason: String },
+    #[error("[{id}] tag {name} is repeated")]
+    DuplicateTag { id: Base64, name: String },
+    #[error("{size} bytes of trailing data follow the last DataItem")]
+    TrailingData { size: u64 },
+}
+
+impl BundleStreamOptions {
+    // the only way parse warnings are reported, so that strict mode fails on all of them
+    fn report(&self, warning: ParseWarning) -> anyhow::Result<()> {
+        if self.strict {
+            return Err(warning.into());
+        }
+        eprintln!("WARNING: {warning}");
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SpillOptions {
+    /// DataItems bigger than this (as declared by bundle table) get their data spilled
+    pub threshold: usize,
+    /// data goes into `<file_prefix>.<DataItem_ID>.data` file
+    pub file_prefix: String,
+}
+
+pub async fn read_data_item<R>(reader: R) -> anyhow::Result<DataItem>
 where
     R: AsyncRead + Unpin,
 {
-    let signature_type = reader.read_u16_le().await.context("signature type")?;
-    let (signature_name, sig_length, pub_key_length) = match signature_type {
-        1 => ("arweave", 512, 512),
-        2 => ("ed25519", 64, 32),
-        3 => ("ethereum", 65, 65),
-        4 => ("solana", 64, 32),
-        v => return Err(anyhow::anyhow!("Unsupported signature type: {v}")),
-    };
-    // signature type 1 has 512 bytes signature
-    let signature = read_buffer_as_base64(&mut reader, sig_length)
-        .await
-        .context("signature")?;
+    read_data_item_hashed(reader, false).await
+}
 
-    let bundle_id = Base64::from(&sha256(&signature.0)[..]);
+// data is hashed on the fly if `hash_data` is set
+async fn read_data_item_hashed<R>(mut reader: R, hash_data: bool) -> anyhow::Result<DataItem>
+where
+    R: AsyncRead + Unpin,
+{
+    let mut data_item = read_data_item_header(&mut reader).await?;
 
-    let owner_public_key = read_buffer_as_base64(&mut reader, pub_key_length)
-        .await
-        .context("owner public key")?;
+    let mut reader = HashingReader::new(reader, hash_data);
+    let mut data = Vec::with_capacity(1024); // allocate 1kbytes initially
+    let _ = reader.read_to_end(&mut data).await.context("data field")?;
+    data_item.data = Base64(data);
+    data_item.data_sha256 = reader.finish();
 
-    let target = read_optional_field_as_base64(&mut reader, 32)
-        .await
-        .context("target")?;
+    Ok(data_item)
+}
 
-    let anchor = read_optional_field_as_base64(&mut reader, 32)
+// reads data of DataItem straight into a file
+async fn read_data_item_spilled<R>(
+    mut reader: R,
+    spill: &SpillOptions,
+    hash_data: bool,
+) -> anyhow::Result<DataItem>
+where
+    R: AsyncRead + Unpin,
+{
+    let mut data_item = read_data_item_header(&mut reader).await?;
+    let mut reader = HashingReader::new(reader, hash_data);
+
+    let path = format!("{}.{}.data", spill.file_prefix, data_item.bundle_id);
+    let mut writer = tokio::io::BufWriter::new(
+        tokio::fs::File::create(&path)
+            .await
+            .with_context(|| format!("spill file {path} create"))?,
+    );
+    let size = tokio::io::copy(&mut reader, &mut writer)
         .await
-        .context("anchor")?;
+        .context("data field")?;
+    writer.flush().await?;
+    data_item.data_file = Some(DataFile {
+        path,
+        size: usize::try_from(size)?,
+    });
+    data_item.data_sha256 = reader.finish();
 
-    let tag_count = reader.read_u64_le().await.context("tag count")?;
+    Ok(data_item)
+}
 
-    let tags_size = reader.read_u64_le().await.context("tags_size")?;
+// feeds every byte read through it into SHA-256 hasher, if there is one
+struct HashingReader<R> {
+    reader: R,
+    hasher: Option<sha2::Sha256>,
+}
 
-    let tags = if tags_size > 0 {
-        let mut tag_data = vec![0; tags_size as usize];
-        reader
-            .read_exact(tag_data.as_mut_slice())
-            .await
-            .context("tag data")?;
+impl<R> HashingReader<R> {
+    fn new(reader: R, hash: bool) -> Self {
+        Self {
+            reader,
+            hasher: hash.then(sha2::Sha256::new),
+        }
+    }
 
-        avro::parse_tag_list(tag_data.as_slice()).context("Avro tags parse")?
-    } else {
-        vec![]
-    };
+    // hex encoded digest of everything read
+    fn finish(self) -> Option<String> {
+        self.hasher.map(|hasher| hex::encode(hasher.finalize()))
+    }
+}
 
-    assert_eq!(tag_count as usize, tags.len());
+impl<R> AsyncRead for HashingReader<R>
+where
+    R: AsyncRead + Unpin,
+{
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut TaskContext<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<std::io::Result<()>> {
+        let this = self.get_mut();
+        let filled = buf.filled().len();
+        let res = Pin::new(&mut this.reader).poll_read(cx, buf);
+        if let (Poll::Ready(Ok(())), Some(hasher)) = (&res, &mut this.hasher) {
+            hasher.update(&buf.filled()[filled..]);
+        }
+        res
+    }
+}
 
-    let mut data = Vec::with_capacity(1024); // allocate 1kbytes initially
-    let _ = reader.read_to_end(&mut data).await.context("data field")?;
+// error of the DataItem itself rather than of the bundle stream: DataItem ending early within
+// its bundle table size leaves nothing `unread`, while failed or truncated stream does
+fn is_invalid_item(err: &anyhow::Error, unread: u64) -> bool {
+    err.chain()
+        .all(|cause| match cause.downcast_ref::<std::io::Error>() {
+            Some(io_err) => io_err.kind() == std::io::ErrorKind::UnexpectedEof && unread == 0,
+            None => true,
+        })
+}
+
+// reads all DataItem fields except data which is left empty
+async fn read_data_item_header<R>(mut reader: R) -> anyhow::Result<DataItem>
+where
+    R: AsyncRead + Unpin,
+{
+    let signature_type = u16::from_le_bytes(read_array(&mut reader, "signature type").await?);
+    let scheme = SignatureScheme::from_type(signature_type)?;
+    let SignatureHeader {
+        signature,
+        owner_public_key,
+    } = scheme.read_header(&mut reader).await?;
+
+    let bundle_id = scheme.derive_id(&signature);
+
+    let target = read_optional_field_as_base64(&mut reader, 32, "target").await?;
+
+    let anchor = read_optional_field_as_base64(&mut reader, 32, "anchor").await?;
+
+    let tag_count = u64::from_le_bytes(read_array(&mut reader, "tag count").await?);
+
+    let tags_size = u64::from_le_bytes(read_array(&mut reader, "tags size").await?);
+
+    // declared size is not trusted, buffer grows only with bytes which actually arrive
+    let mut raw_tags = vec![];
+    (&mut reader)
+        .take(tags_size)
+        .read_to_end(&mut raw_tags)
+        .await?;
+    if (raw_tags.len() as u64) < tags_size {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::UnexpectedEof,
+            format!(
+                "tag data: expected {tags_size} bytes, but stream ended after {}",
+                raw_tags.len()
+            ),
+        )
+        .into());
+    }
+    let tags = avro::parse_tag_list_checked(&raw_tags, tag_count)?;
 
     Ok(DataItem {
-        signature_name: signature_name.to_string(),
+        signature_name: scheme.name().to_string(),
+        signature_type,
         signature,
         bundle_id,
         owner_public_key,
         target,
         anchor,
         tags,
-        data: Base64(data),
+        data: Base64::empty(),
+        data_file: None,
+        data_sha256: None,
+        raw_tags,
+        position: None,
     })
 }
 
-pub fn ans104_bundle_data_item_stream<R>(
+pub fn ans104_bundle_data_item_stream<R>(reader: R) -> impl Stream<Item = anyhow::Result<DataItem>>
+where
+    R: AsyncRead + Unpin,
+{
+    ans104_bundle_data_item_stream_with_options(reader, BundleStreamOptions::default())
+}
+
+pub fn ans104_bundle_data_item_stream_with_options<R>(
     mut reader: R,
+    options: BundleStreamOptions,
 ) -> impl Stream<Item = anyhow::Result<DataItem>>
 where
     R: AsyncRead + Unpin,
 {
     try_stream! {
-        let total_items = read_u256_as_u128(&mut reader).await.context("total DataItems read")?;
+        let total_items = read_bundle_item_count(&mut reader).await?;
+        if total_items == 0 {
+            options.report(ParseWarning::EmptyBundle)?;
+        }
         let data_items_table = read_data_item_and_entry_id_table(&mut reader, total_items).await.context("DataItems table read")?;
         let total = data_items_table.len();
+        if let Some(expected_size) = options.expected_size {
+            verify_bundle_size(&data_items_table, expected_size)?;
+        }
+        if let Some(max_item_size) = options.max_item_size {
+            verify_item_sizes(&data_items_table, max_item_size)?;
+        }
 
-        for (idx, (data_item_size, _)) in data_items_table.into_iter().enumerate() {
+        // DataItems follow item count and table entries of 64 bytes each
+        let mut offset = 32 + 64 * total as u64;
+        let mut _permit = None;
+        for (idx, (data_item_size, entry_id)) in data_items_table.into_iter().enumerate() {
+            let position = ItemPosition {
+                index: idx,
+                offset,
+                length: u64::try_from(data_item_size).context(format!("DataItem {idx} of {total} size"))?,
+            };
+            offset += position.length;
+            // previous DataItem is processed by now
+            _permit = None;
             let mut data_item_reader = (&mut reader).take(data_item_size as u64);
-            let data_item = read_data_item(&mut data_item_reader).await.context(format!("DataItem {idx} of {total}  (size: {data_item_size}) read"))?;
+            let data_item = match &options.spill {
+                Some(spill) if data_item_size > spill.threshold as u128 => read_data_item_spilled(&mut data_item_reader, spill, options.hash_data).await,
+                _ => {
+                    if let Some(mem_budget) = &options.mem_budget {
+                        let size = usize::try_from(data_item_size)?;
+                        _permit = Some(mem_budget.acquire(size).await.context(format!("DataItem {idx} of {total} memory budget"))?);
+                    }
+                    read_data_item_hashed(&mut data_item_reader, options.hash_data).await
+                }
+            }.context(format!("DataItem {idx} of {total}  (size: {data_item_size}) read"));
+            let data_item = match data_item {
+                Err(err) if options.skip_invalid_items && is_invalid_item(&err, data_item_reader.limit()) => {
+                    options.report(ParseWarning::InvalidItemSkipped { entry_id: entry_id.clone(), reason: format!("{err:#}") })?;
+                    // rest of it, next DataItem follows
+                    tokio::io::copy(&mut data_item_reader, &mut tokio::io::sink()).await.context(format!("DataItem {idx} of {total} skip"))?;
+                    continue;
+                }
+                data_item => data_item?,
+            };
+            let data_item = DataItem { position: Some(position), ..data_item };
+            if options.verify_ids && (!data_item.verify_id() || data_item.bundle_id != entry_id) {
+                Err(anyhow::anyhow!("DataItem {idx} of {total} id mismatch: computed {} but bundle table has {entry_id}", data_item.bundle_id))?;
+            }
+            if options.warn_duplicate_tags || options.strict {
+                for name in data_item.duplicate_tag_names() {
+                    options.report(ParseWarning::DuplicateTag { id: data_item.bundle_id.clone(), name: name.to_string() })?;
+                }
+            }
             yield data_item
         }
+        let trailing = tokio::io::copy(&mut reader, &mut tokio::io::sink()).await.context("trailing data read")?;
+        if trailing > 0 {
+            options.report(ParseWarning::TrailingData { size: trailing })?;
+        }
 
     }
 }
 
+pub async fn read_bundle_item_count<R>(reader: R) -> anyhow::Result<u128>
+where
+    R: AsyncRead + Unpin,
+{
+    read_u256_as_u128(reader, "item count")
+        .await
+        .context("total DataItems read")
+}
+
+/// Reads only bundle header and DataItem table returning DataItem ids in bundle order.
+pub async fn read_bundle_item_ids<R>(reader: R) -> anyhow::Result<Vec<Base64>>
+where
+    R: AsyncRead + Unpin,
+{
+    let data_items_table = read_bundle_item_table(reader).await?;
+    Ok(data_items_table.into_iter().map(|(_, id)| id).collect())
+}
+
+/// Reads only bundle header and DataItem table returning size and id of every DataItem in bundle order.
+pub async fn read_bundle_item_table<R>(mut reader: R) -> anyhow::Result<Vec<(u128, Base64)>>
+where
+    R: AsyncRead + Unpin,
+{
+    let total_items = read_bundle_item_count(&mut reader).await?;
+    read_data_item_and_entry_id_table(&mut reader, total_items)
+        .await
+        .context("DataItems table read")
+}
+
+/// Reads bundle header and DataItem table and checks that they account for `expected_size` bytes.
+pub async fn bundle_size_matches<R>(mut reader: R, expected_size: usize) -> anyhow::Result<bool>
+where
+    R: AsyncRead + Unpin,
+{
+    let total_items = read_bundle_item_count(&mut reader).await?;
+    let data_items_table = read_data_item_and_entry_id_table(&mut reader, total_items)
+        .await
+        .context("DataItems table read")?;
+    Ok(bundle_size(&data_items_table) == expected_size as u128)
+}
+
+// bundle consists of 32 bytes item count, 64 bytes table entry per item and items themselves
+fn bundle_size(data_items_table: &[(u128, Base64)]) -> u128 {
+    let header_size = 32 + 64 * data_items_table.len() as u128;
+    data_items_table
+        .iter()
+        .fold(header_size, |acc, (size, _)| acc.saturating_add(*size))
+}
+
+fn verify_bundle_size(
+    data_items_table: &[(u128, Base64)],
+    expected_size: usize,
+) -> anyhow::Result<()> {
+    let bundle_size = bundle_size(data_items_table);
+    if bundle_size != expected_size as u128 {
+        return Err(anyhow::anyhow!(
+            "Bundle size mismatch: header and DataItem table account for {bundle_size} bytes but transaction data size is {expected_size}"
+        ));
+    }
+    Ok(())
+}
+
+fn verify_item_sizes(
+    data_items_table: &[(u128, Base64)],
+    max_item_size: usize,
+) -> anyhow::Result<()> {
+    for (idx, (size, entry_id)) in data_items_table.iter().enumerate() {
+        if *size > max_item_size as u128 {
+            return Err(anyhow::anyhow!(
+                "DataItem {idx} ({entry_id}) size {size} exceeds maximum item size {max_item_size}"
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Fills the whole buffer like `read_exact`, but stream ending early fails with field name
+/// and number of bytes expected and received. It is still `UnexpectedEof` [`std::io::Error`].
+pub(crate) async fn read_field<R>(
+    mut reader: R,
+    buffer: &mut [u8],
+    name: &str,
+) -> anyhow::Result<()>
+where
+    R: AsyncRead + Unpin,
+{
+    let mut received = 0;
+    while received < buffer.len() {
+        match reader.read(&mut buffer[received..]).await? {
+            0 => {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::UnexpectedEof,
+                    format!(
+                        "{name}: expected {} bytes, but stream ended after {received}",
+                        buffer.len()
+                    ),
+                )
+                .into())
+            }
+            read => received += read,
+        }
+    }
+    Ok(())
+}
+
+async fn read_array<const N: usize, R>(reader: R, name: &str) -> anyhow::Result<[u8; N]>
+where
+    R: AsyncRead + Unpin,
+{
+    let mut array = [0; N];
+    read_field(reader, &mut array, name).await?;
+    Ok(array)
+}
+
 // a little helper to read u256 (32bytes size) integers as u128 (ignoring upper half)
 // because: u128 max value in bytes is theoretical maximum volume size of the ZFS filesystem
 // u256 max value in bits ( u253 in bytes!) is information content of a one-solar-mass black hole.
 // we are safe
-async fn read_u256_as_u128<R>(mut reader: R) -> anyhow::Result<u128>
+async fn read_u256_as_u128<R>(reader: R, name: &str) -> anyhow::Result<u128>
 where
     R: AsyncRead + Unpin,
 {
-    let num = reader.read_u128_le().await?;
-    let upper_half = reader.read_u128_le().await?;
+    let value: [u8; 32] = read_array(reader, name).await?;
+    let (num, upper_half) = value.split_at(16);
+    let num = u128::from_le_bytes(num.try_into()?);
+    let upper_half = u128::from_le_bytes(upper_half.try_into()?);
     // make sure that upper half is zero - otherwise we are dealing with integers bigger than u128
-    debug_assert!(upper_half == 0);
+    if upper_half != 0 {
+        return Err(anyhow::anyhow!("u256 value does not fit into u128"));
+    }
     Ok(num)
 }
 
-async fn read_buffer_as_base64<R>(mut reader: R, size: usize) -> anyhow::Result<Base64>
+pub(crate) async fn read_buffer_as_base64<R>(
+    reader: R,
+    size: usize,
+    name: &str,
+) -> anyhow::Result<Base64>
 where
     R: AsyncRead + Unpin,
 {
     let mut vec = vec![0; size];
-    reader.read_exact(vec.as_mut_slice()).await?;
+    read_field(reader, vec.as_mut_slice(), name).await?;
     Ok(Base64(vec))
 }
 
 async fn read_optional_field_as_base64<R>(
     mut reader: R,
     size: usize,
+    name: &str,
 ) -> anyhow::Result<Option<Base64>>
 where
     R: AsyncRead + Unpin,
 {
-    let is_present = reader.read_u8().await?;
-    assert!(is_present < 2); // either 0 or 1 is allowed
-    Ok(if is_present == 1 {
-        Some(read_buffer_as_base64(reader, size).await?)
-    } else {
-        None
-    })
+    let [is_present] = read_array(&mut reader, &format!("{name} presence byte")).await?;
+    match is_present {
+        0 => Ok(None),
+        1 => Ok(Some(read_buffer_as_base64(reader, size, name).await?)),
+        is_present => Err(anyhow::anyhow!(
+            "{name} presence byte is {is_present}, expected 0 or 1"
+        )),
+    }
 }
 
 async fn read_data_item_and_entry_id_table<R>(
@@ -151,9 +721,15 @@ where
     R: AsyncRead + Unpin,
 {
     let mut res = vec![];
-    for _ in 0..total_items {
-        let size = read_u256_as_u128(&mut reader).await?;
-        let entry_id = read_buffer_as_base64(&mut reader, 32).await?;
+    for idx in 0..total_items {
+        // stream ending early would otherwise look like a bundle of fewer DataItems
+        let table_read = || format!("DataItem table has only {idx} of {total_items} entries");
+        let size = read_u256_as_u128(&mut reader, &format!("DataItem {idx} size"))
+            .await
+            .with_context(table_read)?;
+        let entry_id = read_buffer_as_base64(&mut reader, 32, &format!("DataItem {idx} id"))
+            .await
+            .with_context(table_read)?;
 
         res.push((size, entry_id));
     }
@@ -181,6 +757,39 @@ mod test {
         assert_eq!(data_items.len(), 4);
     }
 
+    #[tokio::test]
+    async fn read_item_count_from_bundle_header() {
+        let hex_str = include_str!("../res/uYpAeGCj8Xe_J0sKiZ_aJ4Zl1zQLgDH5ia-pqtNLJEA_data.hex");
+        let data = hex::decode(hex_str).expect("should parse");
+
+        let count = read_bundle_item_count(&data[..32])
+            .await
+            .expect("should work");
+        assert_eq!(count, 4);
+    }
+
+    #[tokio::test]
+    async fn read_item_ids_from_bundle_table() {
+        let hex_str = include_str!("../res/uYpAeGCj8Xe_J0sKiZ_aJ4Zl1zQLgDH5ia-pqtNLJEA_data.hex");
+        let data = hex::decode(hex_str).expect("should parse");
+
+        let ids = read_bundle_item_ids(data.as_slice())
+            .await
+            .expect("should work");
+        let data_items = ans104_bundle_data_item_stream(data.as_slice())
+            .try_collect::<Vec<DataItem>>()
+            .await
+            .expect("should work");
+
+        assert_eq!(
+            ids,
+            data_items
+                .into_iter()
+                .map(|item| item.bundle_id)
+                .collect::<Vec<_>>()
+        );
+    }
+
     #[tokio::test]
     async fn parse_first_item_in_tx_data_bundle() {
         let hex_str = include_str!("../res/first_item.hex");
@@ -192,6 +801,752 @@ mod test {
         assert_eq!(data_item.data.0.len(), 11904);
     }
 
+    #[tokio::test]
+    async fn parse_sample_tx_data_bundle_with_positions() {
+        let hex_str = include_str!("../res/uYpAeGCj8Xe_J0sKiZ_aJ4Zl1zQLgDH5ia-pqtNLJEA_data.hex");
+        let data = hex::decode(hex_str).expect("should parse");
+        let data_items = ans104_bundle_data_item_stream(data.as_slice())
+            .try_collect::<Vec<DataItem>>()
+            .await
+            .expect("should work");
+
+        for (idx, data_item) in data_items.iter().enumerate() {
+            let position = data_item.position.expect("should be set");
+            assert_eq!(position.index, idx);
+            let start = position.offset as usize;
+            let raw = &data[start..start + position.length as usize];
+            let read = read_data_item(raw).await.expect("should work");
+            assert_eq!(read.bundle_id, data_item.bundle_id);
+        }
+        let last = data_items
+            .last()
+            .and_then(|item| item.position)
+            .expect("should be set");
+        assert_eq!((last.offset + last.length) as usize, data.len());
+    }
+
+    #[tokio::test]
+    async fn parse_sample_tx_data_bundle_with_id_verification() {
+        let hex_str = include_str!("../res/uYpAeGCj8Xe_J0sKiZ_aJ4Zl1zQLgDH5ia-pqtNLJEA_data.hex");
+        let data = hex::decode(hex_str).expect("should parse");
+
+        let options = BundleStreamOptions {
+            verify_ids: true,
+            ..Default::default()
+        };
+        let data_items = ans104_bundle_data_item_stream_with_options(data.as_slice(), options)
+            .try_collect::<Vec<DataItem>>()
+            .await
+            .expect("should work");
+
+        assert_eq!(data_items.len(), 4);
+        assert!(data_items.iter().all(DataItem::verify_id));
+    }
+
+    #[tokio::test]
+    async fn parse_sample_tx_data_bundle_with_size_verification() {
+        let hex_str = include_str!("../res/uYpAeGCj8Xe_J0sKiZ_aJ4Zl1zQLgDH5ia-pqtNLJEA_data.hex");
+        let data = hex::decode(hex_str).expect("should parse");
+
+        let options = BundleStreamOptions {
+            expected_size: Some(data.len()),
+            ..Default::default()
+        };
+        let data_items = ans104_bundle_data_item_stream_with_options(data.as_slice(), options)
+            .try_collect::<Vec<DataItem>>()
+            .await
+            .expect("should work");
+        assert_eq!(data_items.len(), 4);
+
+        for expected_size in [data.len() - 1, data.len() + 1] {
+            let options = BundleStreamOptions {
+                expected_size: Some(expected_size),
+                ..Default::default()
+            };
+            let res = ans104_bundle_data_item_stream_with_options(data.as_slice(), options)
+                .try_collect::<Vec<DataItem>>()
+                .await;
+            assert!(res.is_err());
+        }
+    }
+
+    #[tokio::test]
+    async fn parse_bundle_with_max_item_size() {
+        let hex_str = include_str!("../res/uYpAeGCj8Xe_J0sKiZ_aJ4Zl1zQLgDH5ia-pqtNLJEA_data.hex");
+        let data = hex::decode(hex_str).expect("should parse");
+        let options = BundleStreamOptions {
+            max_item_size: Some(data.len()),
+            ..Default::default()
+        };
+        let data_items = ans104_bundle_data_item_stream_with_options(data.as_slice(), options)
+            .try_collect::<Vec<DataItem>>()
+            .await
+            .expect("should work");
+        assert_eq!(data_items.len(), 4);
+
+        // single DataItem declaring enormous size, followed by a few bytes only
+        let mut data = u256_le(1);
+        data.extend(u256_le(u64::MAX as u128));
+        data.extend([1u8; 32]);
+        data.extend([0u8; 16]);
+        let options = BundleStreamOptions {
+            max_item_size: Some(1024),
+            ..Default::default()
+        };
+        let err = ans104_bundle_data_item_stream_with_options(data.as_slice(), options)
+            .try_collect::<Vec<DataItem>>()
+            .await
+            .expect_err("should fail");
+        assert!(
+            err.to_string().contains("exceeds maximum item size 1024"),
+            "{err:#}"
+        );
+    }
+
+    #[tokio::test]
+    async fn parse_sample_tx_data_bundle_with_spilled_data() {
+        let hex_str = include_str!("../res/uYpAeGCj8Xe_J0sKiZ_aJ4Zl1zQLgDH5ia-pqtNLJEA_data.hex");
+        let data = hex::decode(hex_str).expect("should parse");
+        let in_memory = ans104_bundle_data_item_stream(data.as_slice())
+            .try_collect::<Vec<DataItem>>()
+            .await
+            .expect("should work");
+
+        let dir = std::env::temp_dir().join(format!("arweave-dumper-spill-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).expect("should create dir");
+        let options = BundleStreamOptions {
+            spill: Some(SpillOptions {
+                threshold: 0,
+                file_prefix: dir.join("bundle").to_string_lossy().to_string(),
+            }),
+            ..Default::default()
+        };
+        let spilled = ans104_bundle_data_item_stream_with_options(data.as_slice(), options)
+            .try_collect::<Vec<DataItem>>()
+            .await
+            .expect("should work");
+
+        assert_eq!(spilled.len(), in_memory.len());
+        for (spilled, in_memory) in spilled.iter().zip(&in_memory) {
+            assert!(spilled.data.is_empty());
+            assert_eq!(spilled.tags, in_memory.tags);
+            let data_file = spilled.data_file.as_ref().expect("should be spilled");
+            assert_eq!(data_file.size, in_memory.data.0.len());
+            assert_eq!(
+                std::fs::read(&data_file.path).expect("should read"),
+                in_memory.data.0
+            );
+        }
+        std::fs::remove_dir_all(dir).expect("should remove dir");
+    }
+
+    #[tokio::test]
+    async fn parse_sample_tx_data_bundle_with_data_hashes() {
+        let hex_str = include_str!("../res/uYpAeGCj8Xe_J0sKiZ_aJ4Zl1zQLgDH5ia-pqtNLJEA_data.hex");
+        let data = hex::decode(hex_str).expect("should parse");
+        let options = BundleStreamOptions {
+            hash_data: true,
+            ..Default::default()
+        };
+        let in_memory = ans104_bundle_data_item_stream_with_options(data.as_slice(), options)
+            .try_collect::<Vec<DataItem>>()
+            .await
+            .expect("should work");
+
+        let dir = std::env::temp_dir().join(format!("arweave-dumper-hash-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).expect("should create dir");
+        let options = BundleStreamOptions {
+            hash_data: true,
+            spill: Some(SpillOptions {
+                threshold: 0,
+                file_prefix: dir.join("bundle").to_string_lossy().to_string(),
+            }),
+            ..Default::default()
+        };
+        let spilled = ans104_bundle_data_item_stream_with_options(data.as_slice(), options)
+            .try_collect::<Vec<DataItem>>()
+            .await
+            .expect("should work");
+        std::fs::remove_dir_all(dir).expect("should remove dir");
+
+        for (spilled, in_memory) in spilled.iter().zip(&in_memory) {
+            let expected = hex::encode(sha256(&in_memory.data.0));
+            assert_eq!(in_memory.data_sha256.as_ref(), Some(&expected));
+            assert_eq!(spilled.data_sha256.as_ref(), Some(&expected));
+        }
+    }
+
+    #[tokio::test]
+    async fn parse_sample_tx_data_bundle_within_memory_budget() {
+        let hex_str = include_str!("../res/uYpAeGCj8Xe_J0sKiZ_aJ4Zl1zQLgDH5ia-pqtNLJEA_data.hex");
+        let data = hex::decode(hex_str).expect("should parse");
+
+        // one DataItem at a time fits, permit of the previous one is released on next poll
+        let options = BundleStreamOptions {
+            mem_budget: Some(MemoryBudget::new(16 * 1024).expect("should create")),
+            ..Default::default()
+        };
+        let data_items = ans104_bundle_data_item_stream_with_options(data.as_slice(), options)
+            .try_collect::<Vec<DataItem>>()
+            .await
+            .expect("should work");
+        assert_eq!(data_items.len(), 4);
+
+        let options = BundleStreamOptions {
+            mem_budget: Some(MemoryBudget::new(1024).expect("should create")),
+            ..Default::default()
+        };
+        let res = ans104_bundle_data_item_stream_with_options(data.as_slice(), options)
+            .try_collect::<Vec<DataItem>>()
+            .await;
+        assert!(res.is_err());
+    }
+
+    #[tokio::test]
+    async fn tampered_table_entry_id_fails_verification() {
+        let hex_str = include_str!("../res/uYpAeGCj8Xe_J0sKiZ_aJ4Zl1zQLgDH5ia-pqtNLJEA_data.hex");
+        let mut data = hex::decode(hex_str).expect("should parse");
+        // first table entry id starts after item count (32 bytes) and first item size (32 bytes)
+        data[64] ^= 0xff;
+
+        let options = BundleStreamOptions {
+            verify_ids: true,
+            ..Default::default()
+        };
+        let res = ans104_bundle_data_item_stream_with_options(data.as_slice(), options)
+            .try_collect::<Vec<DataItem>>()
+            .await;
+        assert!(res.is_err());
+
+        // without verification tampered table ids go unnoticed
+        let data_items = ans104_bundle_data_item_stream(data.as_slice())
+            .try_collect::<Vec<DataItem>>()
+            .await
+            .expect("should work");
+        assert_eq!(data_items.len(), 4);
+    }
+
+    #[tokio::test]
+    async fn tampered_id_fails_verify_id() {
+        let hex_str = include_str!("../res/first_item.hex");
+        let data = hex::decode(hex_str).expect("should parse");
+
+        let mut data_item = read_data_item(data.as_slice()).await.expect("should work");
+        assert!(data_item.verify_id());
+
+        data_item.bundle_id.0[0] ^= 0xff;
+        assert!(!data_item.verify_id());
+    }
+
+    fn u256_le(value: u128) -> Vec<u8> {
+        let mut bytes = value.to_le_bytes().to_vec();
+        bytes.extend_from_slice(&[0u8; 16]);
+        bytes
+    }
+
+    #[tokio::test]
+    async fn read_u256_size() {
+        let data = u256_le(0x0102_0304);
+        let num = read_u256_as_u128(data.as_slice(), "test")
+            .await
+            .expect("should not fail");
+        assert_eq!(num, 0x0102_0304);
+    }
+
+    #[tokio::test]
+    async fn read_u256_with_nonzero_upper_half_fails() {
+        let mut data = u256_le(1);
+        data[31] = 1;
+        assert!(read_u256_as_u128(data.as_slice(), "test").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn read_data_item_table_with_multiple_entries() {
+        let mut data = vec![];
+        for (size, id_byte) in [(100u128, 1u8), (u64::MAX as u128 + 1, 2), (0, 3)] {
+            data.extend(u256_le(size));
+            data.extend([id_byte; 32]);
+        }
+
+        let table = read_data_item_and_entry_id_table(data.as_slice(), 3)
+            .await
+            .expect("should not fail");
+        assert_eq!(
+            table,
+            vec![
+                (100, Base64(vec![1; 32])),
+                (u64::MAX as u128 + 1, Base64(vec![2; 32])),
+                (0, Base64(vec![3; 32])),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn read_data_item_table_too_short() {
+        let mut data = u256_le(100);
+        data.extend([1u8; 32]);
+        let err = read_data_item_and_entry_id_table(data.as_slice(), 2)
+            .await
+            .expect_err("should fail");
+        assert_eq!(
+            format!("{err:#}"),
+            "DataItem table has only 1 of 2 entries: DataItem 1 size: expected 32 bytes, but stream ended after 0"
+        );
+        assert!(err
+            .downcast_ref::<std::io::Error>()
+            .is_some_and(|err| err.kind() == std::io::ErrorKind::UnexpectedEof));
+    }
+
+    #[tokio::test]
+    async fn parse_bundle_with_truncated_table() {
+        let mut data = u256_le(3);
+        data.extend(u256_le(100));
+        data.extend([1u8; 32]);
+        // second entry is cut in the middle of its id
+        data.extend(u256_le(100));
+        data.extend([2u8; 10]);
+
+        let err = ans104_bundle_data_item_stream(data.as_slice())
+            .try_collect::<Vec<_>>()
+            .await
+            .expect_err("should fail");
+        assert!(
+            format!("{err:#}").contains("DataItem table has only 1 of 3 entries: DataItem 1 id"),
+            "{err:#}"
+        );
+    }
+
+    #[tokio::test]
+    async fn truncated_data_item_reports_field() {
+        let hex_str = include_str!("../res/first_item.hex");
+        let data = hex::decode(hex_str).expect("should parse");
+
+        // signature type, RSA signature and owner, no target, anchor, tag count and size, part of tags
+        let truncated = &data[..2 + 512 + 512 + 1 + 1 + 32 + 8 + 8 + 10];
+        let err = read_data_item(truncated).await.expect_err("should fail");
+        assert!(format!("{err:#}").contains("tag data: expected"), "{err:#}");
+        let err = read_data_item(&data[..100]).await.expect_err("should fail");
+        assert_eq!(
+            format!("{err:#}"),
+            "signature: expected 512 bytes, but stream ended after 98"
+        );
+    }
+
+    fn data_item_with_owner(signature_name: &str, owner: Vec<u8>) -> DataItem {
+        DataItem {
+            signature_name: signature_name.to_string(),
+            signature_type: SignatureScheme::from_name(signature_name)
+                .map_or(0, SignatureScheme::signature_type),
+            signature: Base64::empty(),
+            bundle_id: Base64::empty(),
+            owner_public_key: Base64(owner),
+            target: None,
+            anchor: None,
+            tags: vec![],
+            data: Base64::empty(),
+            data_file: None,
+            data_sha256: None,
+            raw_tags: vec![],
+            position: None,
+        }
+    }
+
+    // ed25519 DataItem with anchor and one tag, signed by test key
+    fn signed_ed25519_item(data: &[u8]) -> Vec<u8> {
+        let secret_key = [7u8; 32];
+        let (owner, _) = ed25519::test::sign(&secret_key, b"");
+        let mut unsigned = data_item_with_owner("ed25519", owner.to_vec());
+        unsigned.anchor = Some(Base64(vec![1; 32]));
+        unsigned.tags = vec![BundleTag {
+            name: "a".to_string(),
+            value: "b".to_string(),
+        }];
+        unsigned.raw_tags = vec![0x02, 0x02, b'a', 0x02, b'b', 0x00];
+        unsigned.data = Base64(data.to_vec());
+        let message = unsigned.signature_message().expect("should be available");
+        let (_, signature) = ed25519::test::sign(&secret_key, &message);
+
+        let mut item = 2u16.to_le_bytes().to_vec();
+        item.extend(signature);
+        item.extend(owner);
+        item.push(0); // no target
+        item.push(1);
+        item.extend([1; 32]);
+        item.extend(1u64.to_le_bytes());
+        item.extend((unsigned.raw_tags.len() as u64).to_le_bytes());
+        item.extend(&unsigned.raw_tags);
+        item.extend(data);
+        item
+    }
+
+    #[tokio::test]
+    async fn test_verify_ed25519() {
+        let mut item = signed_ed25519_item(b"hello");
+        let data_item = read_data_item(item.as_slice()).await.expect("should parse");
+        assert_eq!(data_item.get_tag("a"), Some("b"));
+        assert_eq!(data_item.verify_ed25519(), Some(true));
+
+        *item.last_mut().expect("should have data") ^= 1;
+        let data_item = read_data_item(item.as_slice()).await.expect("should parse");
+        assert_eq!(data_item.verify_ed25519(), Some(false));
+
+        let hex_str = include_str!("../res/first_item.hex");
+        let data = hex::decode(hex_str).expect("should parse");
+        let data_item = read_data_item(data.as_slice()).await.expect("should parse");
+        assert_eq!(data_item.verify_ed25519(), None);
+    }
+
+    // bundle signed with solana wallet by arbundles, from bundlr-sdk test bundles
+    #[tokio::test]
+    async fn test_verify_solana_bundle_item() {
+        let hex_str = include_str!("../res/solana_sig_bundle.hex");
+        let data = hex::decode(hex_str).expect("should parse");
+        let data_items = ans104_bundle_data_item_stream(data.as_slice())
+            .try_collect::<Vec<DataItem>>()
+            .await
+            .expect("should work");
+        assert_eq!(data_items.len(), 1);
+        let mut data_item = data_items.into_iter().next().expect("should have item");
+        assert_eq!(data_item.signature_type, 2);
+        assert!(data_item.verify_id());
+        assert_eq!(data_item.verify_ed25519(), Some(true));
+
+        data_item.data.0[0] ^= 1;
+        assert_eq!(data_item.verify_ed25519(), Some(false));
+    }
+
+    #[tokio::test]
+    async fn test_verify_rsa() {
+        let hex_str = include_str!("../res/first_item.hex");
+        let data = hex::decode(hex_str).expect("should parse");
+        let data_item = read_data_item(data.as_slice()).await.expect("should parse");
+        assert!(data_item.verify_id());
+        assert_eq!(data_item.verify_rsa(), Some(true));
+
+        let mut tampered = data_item;
+        tampered.data.0[0] ^= 1;
+        assert_eq!(tampered.verify_rsa(), Some(false));
+
+        let data_item = read_data_item(signed_ed25519_item(b"hello").as_slice())
+            .await
+            .expect("should parse");
+        assert_eq!(data_item.verify_rsa(), None);
+    }
+
+    #[test]
+    fn test_content_type_matches() {
+        let patterns: Vec<_> = ["image/*", "video/mp4"]
+            .into_iter()
+            .map(|pattern| ContentTypePattern::new(pattern).expect("should parse"))
+            .collect();
+        let mut data_item = data_item_with_owner("ed25519", vec![]);
+        assert!(!data_item.content_type_matches(&patterns));
+
+        for (content_type, matches) in [
+            ("image/png", true),
+            ("Image/JPEG; quality=high", true),
+            ("video/mp4", true),
+            ("video/webm", false),
+            ("text/plain", false),
+        ] {
+            data_item.tags = vec![BundleTag {
+                name: "Content-Type".to_string(),
+                value: content_type.to_string(),
+            }];
+            assert_eq!(
+                data_item.content_type_matches(&patterns),
+                matches,
+                "{content_type}"
+            );
+        }
+
+        let any = ContentTypePattern::new("*").expect("should parse");
+        assert!(data_item.content_type_matches(&[any]));
+        assert!(ContentTypePattern::new("image/*/png").is_err());
+        assert!(ContentTypePattern::new("*/png").is_err());
+        assert!(ContentTypePattern::new("").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_signature_type_id() {
+        let hex_str = include_str!("../res/first_item.hex");
+        let data = hex::decode(hex_str).expect("should parse");
+        let data_item = read_data_item(data.as_slice()).await.expect("should parse");
+        assert_eq!(data_item.signature_type, 1);
+        assert_eq!(data_item.signature_type_id(), Some(1));
+
+        let mut value = serde_json::to_value(&data_item).expect("should serialize");
+        assert_eq!(value["signature_type"], 1);
+        value
+            .as_object_mut()
+            .expect("should be object")
+            .remove("signature_type");
+        let data_item: DataItem = serde_json::from_value(value).expect("should parse");
+        assert_eq!(data_item.signature_type, 0);
+        assert_eq!(data_item.signature_type_id(), Some(1));
+    }
+
+    #[test]
+    fn test_debug_elides_data() {
+        let mut data_item = data_item_with_owner("ed25519", vec![]);
+        data_item.data = Base64(vec![b'x'; 1024 * 1024]);
+
+        let debug = format!("{data_item:?}");
+        assert!(debug.contains("data: <1048576 bytes>"), "{debug}");
+        assert!(debug.len() < 1024, "{debug}");
+    }
+
+    #[test]
+    fn test_ethereum_owner_address() {
+        // public key of the private key 0x01
+        let owner = hex::decode("0479be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798483ada7726a3c4655da4fbfc0e1108a8fd17b448a68554199c47d08ffb10d4b8").expect("should parse");
+        let data_item = data_item_with_owner("ethereum", owner);
+        assert_eq!(
+            data_item.owner_address().as_deref(),
+            Some("0x7e5f4552091a69125d5dfcb7b8c2659029395bdf")
+        );
+    }
+
+    #[test]
+    fn test_solana_owner_address() {
+        let data_item = data_item_with_owner("solana", vec![0; 32]);
+        assert_eq!(
+            data_item.owner_address().as_deref(),
+            Some("11111111111111111111111111111111")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_arweave_owner_address() {
+        let hex_str = include_str!("../res/first_item.hex");
+        let data = hex::decode(hex_str).expect("should parse");
+
+        let data_item = read_data_item(data.as_slice()).await.expect("should work");
+        let expected = Base64::from(&sha256(&data_item.owner_public_key.0)[..]).to_string();
+        assert_eq!(data_item.owner_address(), Some(expected));
+    }
+
+    #[test]
+    fn test_ed25519_has_no_owner_address() {
+        let data_item = data_item_with_owner("ed25519", vec![0; 32]);
+        assert_eq!(data_item.owner_address(), None);
+    }
+
+    #[test]
+    fn test_duplicate_tag_names() {
+        let mut data_item = data_item_with_owner("arweave", vec![]);
+        data_item.tags = ["App-Name", "Type", "App-Name", "Type", "App-Name", "Other"]
+            .into_iter()
+            .map(|name| BundleTag {
+                name: name.to_string(),
+                value: "value".to_string(),
+            })
+            .collect();
+        assert_eq!(data_item.duplicate_tag_names(), vec!["App-Name", "Type"]);
+
+        data_item.tags.truncate(2);
+        assert!(data_item.duplicate_tag_names().is_empty());
+        assert!(data_item.has_tag("Type"));
+        assert!(!data_item.has_tag("Other"));
+    }
+
+    #[tokio::test]
+    async fn zero_tags_size_with_nonzero_tag_count_fails() {
+        let hex_str = include_str!("../res/zero_tags_nonzero_count.hex");
+        let data = hex::decode(hex_str).expect("should parse");
+
+        let res = read_data_item(data.as_slice()).await;
+        assert!(res.is_err());
+    }
+
+    #[tokio::test]
+    async fn huge_declared_tags_size_fails_without_allocating_it() {
+        let mut item = signed_ed25519_item(b"hello");
+        // after signature type, signature, owner, target, anchor and tag count
+        let tags_size_offset = 2 + 64 + 32 + 1 + 33 + 8;
+        item[tags_size_offset..tags_size_offset + 8].copy_from_slice(&(1u64 << 40).to_le_bytes());
+
+        let err = read_data_item(item.as_slice())
+            .await
+            .expect_err("should fail");
+        let io_err = err
+            .downcast_ref::<std::io::Error>()
+            .expect("should be io error");
+        assert_eq!(io_err.kind(), std::io::ErrorKind::UnexpectedEof);
+        assert!(err.to_string().contains("tag data"), "{err:#}");
+    }
+
+    #[tokio::test]
+    async fn zero_tag_count_with_nonzero_tags_size_fails() {
+        let hex_str = include_str!("../res/zero_tag_count_nonzero_tags_size.hex");
+        let data = hex::decode(hex_str).expect("should parse");
+
+        let err = read_data_item(data.as_slice())
+            .await
+            .expect_err("should fail");
+        assert!(err
+            .to_string()
+            .contains("tag count is 0 but tags size is 6"));
+    }
+
+    #[tokio::test]
+    async fn parse_empty_bundle() {
+        let data = hex::decode(include_str!("../res/empty_bundle.hex")).expect("should parse");
+        let options = BundleStreamOptions {
+            expected_size: Some(data.len()),
+            ..Default::default()
+        };
+        let data_items = ans104_bundle_data_item_stream_with_options(data.as_slice(), options)
+            .try_collect::<Vec<DataItem>>()
+            .await
+            .expect("should work");
+        assert!(data_items.is_empty());
+
+        let options = BundleStreamOptions {
+            strict: true,
+            ..Default::default()
+        };
+        let err = ans104_bundle_data_item_stream_with_options(data.as_slice(), options)
+            .try_collect::<Vec<DataItem>>()
+            .await
+            .expect_err("should fail");
+        assert!(matches!(
+            err.downcast_ref::<ParseWarning>(),
+            Some(ParseWarning::EmptyBundle)
+        ));
+    }
+
+    #[tokio::test]
+    async fn parse_bundle_with_trailing_data() {
+        let item = signed_ed25519_item(b"hello");
+        let mut data = u256_le(1);
+        data.extend(u256_le(item.len() as u128));
+        data.extend([0; 32]);
+        data.extend(&item);
+        data.extend([0; 10]);
+
+        let data_items = ans104_bundle_data_item_stream(data.as_slice())
+            .try_collect::<Vec<DataItem>>()
+            .await
+            .expect("should work");
+        assert_eq!(data_items.len(), 1);
+
+        let options = BundleStreamOptions {
+            strict: true,
+            ..Default::default()
+        };
+        let err = ans104_bundle_data_item_stream_with_options(data.as_slice(), options)
+            .try_collect::<Vec<DataItem>>()
+            .await
+            .expect_err("should fail");
+        assert!(
+            matches!(
+                err.downcast_ref::<ParseWarning>(),
+                Some(ParseWarning::TrailingData { size: 10 })
+            ),
+            "{err:#}"
+        );
+    }
+
+    #[tokio::test]
+    async fn parse_sample_tx_data_bundle_strictly() {
+        let hex_str = include_str!("../res/uYpAeGCj8Xe_J0sKiZ_aJ4Zl1zQLgDH5ia-pqtNLJEA_data.hex");
+        let data = hex::decode(hex_str).expect("should parse");
+        let options = BundleStreamOptions {
+            strict: true,
+            ..Default::default()
+        };
+        // fixture DataItems repeat App-Name tag
+        let err = ans104_bundle_data_item_stream_with_options(data.as_slice(), options)
+            .try_collect::<Vec<DataItem>>()
+            .await
+            .expect_err("should fail");
+        assert!(matches!(
+            err.downcast_ref::<ParseWarning>(),
+            Some(ParseWarning::DuplicateTag { name, .. }) if name == "App-Name"
+        ));
+    }
+
+    #[tokio::test]
+    async fn parse_item_with_zero_length_data() {
+        let hex_str = include_str!("../res/zero_length_data.hex");
+        let data = hex::decode(hex_str).expect("should parse");
+
+        let data_item = read_data_item(data.as_slice()).await.expect("should work");
+        assert_eq!(
+            data_item.tags,
+            vec![BundleTag {
+                name: "a".to_string(),
+                value: "b".to_string()
+            }]
+        );
+        assert!(data_item.data.is_empty());
+    }
+
+    #[tokio::test]
+    async fn invalid_target_presence_byte_fails() {
+        let hex_str = include_str!("../res/invalid_target_presence.hex");
+        let data = hex::decode(hex_str).expect("should parse");
+
+        let err = read_data_item(data.as_slice())
+            .await
+            .expect_err("should fail");
+        assert!(format!("{err:#}").contains("target presence byte is 2, expected 0 or 1"));
+    }
+
+    #[tokio::test]
+    async fn invalid_item_is_skipped_in_lenient_mode() {
+        let valid = hex::decode(include_str!("../res/zero_length_data.hex")).expect("should parse");
+        let invalid =
+            hex::decode(include_str!("../res/invalid_target_presence.hex")).expect("should parse");
+        // ends within its signature
+        let truncated = valid[..10].to_vec();
+        let items = [&valid, &invalid, &truncated, &valid];
+        let mut data = u256_le(items.len() as u128);
+        for (idx, item) in items.iter().enumerate() {
+            data.extend(u256_le(item.len() as u128));
+            data.extend([idx as u8; 32]);
+        }
+        for item in items {
+            data.extend(item);
+        }
+
+        ans104_bundle_data_item_stream(data.as_slice())
+            .try_collect::<Vec<_>>()
+            .await
+            .expect_err("should fail");
+
+        let options = BundleStreamOptions {
+            skip_invalid_items: true,
+            ..Default::default()
+        };
+        let data_items = ans104_bundle_data_item_stream_with_options(data.as_slice(), options)
+            .try_collect::<Vec<_>>()
+            .await
+            .expect("should skip invalid DataItem");
+        assert_eq!(data_items.len(), 2);
+
+        // bundle stream ending early is not an invalid DataItem
+        let options = BundleStreamOptions {
+            skip_invalid_items: true,
+            ..Default::default()
+        };
+        ans104_bundle_data_item_stream_with_options(&data[..data.len() - 1], options)
+            .try_collect::<Vec<_>>()
+            .await
+            .expect_err("should fail");
+    }
+
+    #[tokio::test]
+    async fn parse_item_with_empty_avro_tag_array() {
+        let hex_str = include_str!("../res/empty_avro_tags.hex");
+        let data = hex::decode(hex_str).expect("should parse");
+
+        let data_item = read_data_item(data.as_slice()).await.expect("should work");
+        assert!(data_item.tags.is_empty());
+        assert_eq!(data_item.data.0, b"data");
+    }
+
     #[tokio::test]
     async fn test_read_to_the_end() {
         let mut data: &[u8] = b"12345";
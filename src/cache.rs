@@ -0,0 +1,111 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::{Arc, Mutex},
+};
+
+/// Least recently used response bodies up to given number of entries, shared by all clones.
+#[derive(Debug, Clone)]
+pub struct MetadataCache {
+    inner: Arc<Mutex<Entries>>,
+}
+
+#[derive(Debug)]
+struct Entries {
+    capacity: usize,
+    // every use of an entry gets next tick, the smallest one is evicted first
+    tick: u64,
+    by_key: HashMap<String, (u64, String)>,
+    by_tick: BTreeMap<u64, String>,
+}
+
+impl MetadataCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(Entries {
+                capacity,
+                tick: 0,
+                by_key: HashMap::new(),
+                by_tick: BTreeMap::new(),
+            })),
+        }
+    }
+
+    pub fn get(&self, key: &str) -> Option<String> {
+        let mut entries = self.inner.lock().expect("should not be poisoned");
+        let tick = entries.next_tick();
+        let (last_used, value) = entries.by_key.get_mut(key)?;
+        let previous = std::mem::replace(last_used, tick);
+        let value = value.clone();
+        entries.by_tick.remove(&previous);
+        entries.by_tick.insert(tick, key.to_string());
+        Some(value)
+    }
+
+    pub fn insert(&self, key: String, value: String) {
+        let mut entries = self.inner.lock().expect("should not be poisoned");
+        if entries.capacity == 0 {
+            return;
+        }
+        let tick = entries.next_tick();
+        if let Some((previous, _)) = entries.by_key.insert(key.clone(), (tick, value)) {
+            entries.by_tick.remove(&previous);
+        }
+        entries.by_tick.insert(tick, key);
+        while entries.by_key.len() > entries.capacity {
+            let Some((_, evicted)) = entries.by_tick.pop_first() else {
+                break;
+            };
+            entries.by_key.remove(&evicted);
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.inner
+            .lock()
+            .expect("should not be poisoned")
+            .by_key
+            .len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl Entries {
+    fn next_tick(&mut self) -> u64 {
+        self.tick += 1;
+        self.tick
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_least_recently_used_is_evicted() {
+        let cache = MetadataCache::new(2);
+        cache.insert("a".to_string(), "1".to_string());
+        cache.insert("b".to_string(), "2".to_string());
+        assert_eq!(cache.get("a").as_deref(), Some("1"));
+
+        cache.insert("c".to_string(), "3".to_string());
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.get("b"), None);
+        assert_eq!(cache.get("a").as_deref(), Some("1"));
+        assert_eq!(cache.get("c").as_deref(), Some("3"));
+
+        cache.insert("c".to_string(), "4".to_string());
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.get("c").as_deref(), Some("4"));
+    }
+
+    #[test]
+    fn test_zero_capacity_caches_nothing() {
+        let cache = MetadataCache::new(0);
+        cache.insert("a".to_string(), "1".to_string());
+        assert!(cache.is_empty());
+        assert_eq!(cache.get("a"), None);
+    }
+}
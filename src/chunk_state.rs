@@ -0,0 +1,200 @@
+use std::path::PathBuf;
+
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use tokio::{
+    io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt},
+    sync::mpsc,
+};
+use tokio_util::bytes::Bytes;
+
+// state is persisted after this many chunks (4 MiB of data)
+const PERSIST_INTERVAL_CHUNKS: usize = 16;
+const REPLAY_BUFFER: usize = 256 * 1024;
+
+/// Persisted progress of transaction data chunk stream, so that interrupted download can be resumed,
+/// see [`crate::arweave::Client::transaction_data_chunk_stream_with_state`].
+/// Fetched data is kept in `<state_file>.data` until the stream completes.
+#[derive(Debug, Clone)]
+pub struct ChunkStateOptions {
+    /// JSON file with offset of the next chunk to fetch
+    pub state_file: PathBuf,
+    /// continue after the last persisted chunk instead of starting over,
+    /// data fetched before is replayed from the data file first
+    pub resume: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ChunkState {
+    /// offsets of transaction data, resumed stream has to be of the same transaction
+    start_offset: usize,
+    end_offset: usize,
+    /// data before this offset is persisted in the data file
+    chunk_offset: usize,
+}
+
+/// Data file and state of a running chunk stream.
+pub(crate) struct ChunkLog {
+    options: ChunkStateOptions,
+    data_path: PathBuf,
+    data: tokio::fs::File,
+    state: ChunkState,
+    unpersisted_chunks: usize,
+}
+
+impl ChunkLog {
+    /// Opens data file, truncated to persisted state when resuming, created empty otherwise.
+    /// Progress notes are not printed when `quiet`.
+    pub(crate) async fn open(
+        options: &ChunkStateOptions,
+        start_offset: usize,
+        end_offset: usize,
+        quiet: bool,
+    ) -> anyhow::Result<Self> {
+        let mut data_path = options.state_file.clone().into_os_string();
+        data_path.push(".data");
+        let data_path = PathBuf::from(data_path);
+
+        let state = match options.resume {
+            true => read_state(options, start_offset, end_offset, quiet).await?,
+            false => None,
+        };
+        let (data, state) = match state {
+            Some(state) => {
+                let data = tokio::fs::OpenOptions::new()
+                    .read(true)
+                    .write(true)
+                    .open(&data_path)
+                    .await
+                    .with_context(|| format!("Chunk data file {}", data_path.display()))?;
+                let persisted = (state.chunk_offset - start_offset) as u64;
+                let data_len = data.metadata().await?.len();
+                if data_len < persisted {
+                    return Err(anyhow::anyhow!(
+                        "Chunk data file {} has {data_len} bytes, but state says {persisted} were fetched",
+                        data_path.display()
+                    ));
+                }
+                // data fetched after the last persisted state is fetched again
+                data.set_len(persisted).await?;
+                if !quiet {
+                    eprintln!(
+                        "Resuming from offset {} ({persisted} of {} bytes fetched)",
+                        state.chunk_offset,
+                        end_offset + 1 - start_offset
+                    );
+                }
+                (data, state)
+            }
+            None => {
+                let data = tokio::fs::OpenOptions::new()
+                    .read(true)
+                    .write(true)
+                    .create(true)
+                    .truncate(true)
+                    .open(&data_path)
+                    .await
+                    .with_context(|| format!("Chunk data file {}", data_path.display()))?;
+                let state = ChunkState {
+                    start_offset,
+                    end_offset,
+                    chunk_offset: start_offset,
+                };
+                (data, state)
+            }
+        };
+        let mut log = Self {
+            options: options.clone(),
+            data_path,
+            data,
+            state,
+            unpersisted_chunks: 0,
+        };
+        log.persist().await?;
+        Ok(log)
+    }
+
+    /// Offset of the next chunk to fetch.
+    pub(crate) fn chunk_offset(&self) -> usize {
+        self.state.chunk_offset
+    }
+
+    /// Sends data fetched before resuming, leaves data file positioned at its end.
+    pub(crate) async fn replay(
+        &mut self,
+        tx: &mpsc::Sender<anyhow::Result<Bytes>>,
+    ) -> anyhow::Result<()> {
+        self.data.seek(std::io::SeekFrom::Start(0)).await?;
+        let mut remaining = self.state.chunk_offset - self.state.start_offset;
+        while remaining > 0 {
+            let mut buffer = vec![0; remaining.min(REPLAY_BUFFER)];
+            self.data.read_exact(&mut buffer).await?;
+            remaining -= buffer.len();
+            tx.send(Ok(Bytes::from(buffer))).await?;
+        }
+        Ok(())
+    }
+
+    pub(crate) async fn append(&mut self, chunk: &[u8]) -> anyhow::Result<()> {
+        self.data.write_all(chunk).await?;
+        self.state.chunk_offset += chunk.len();
+        self.unpersisted_chunks += 1;
+        if self.unpersisted_chunks >= PERSIST_INTERVAL_CHUNKS {
+            self.persist().await?;
+        }
+        Ok(())
+    }
+
+    /// Removes data and state files once all chunks are fetched.
+    pub(crate) async fn finish(self) -> anyhow::Result<()> {
+        drop(self.data);
+        tokio::fs::remove_file(&self.data_path).await?;
+        tokio::fs::remove_file(&self.options.state_file).await?;
+        Ok(())
+    }
+
+    /// Syncs data file and writes state, which never claims more data than is synced.
+    pub(crate) async fn persist(&mut self) -> anyhow::Result<()> {
+        self.data.sync_data().await?;
+        let mut tmp_path = self.options.state_file.clone().into_os_string();
+        tmp_path.push(".tmp");
+        tokio::fs::write(&tmp_path, serde_json::to_vec(&self.state)?).await?;
+        tokio::fs::rename(&tmp_path, &self.options.state_file).await?;
+        self.unpersisted_chunks = 0;
+        Ok(())
+    }
+}
+
+// missing state file starts download over, state of other transaction data fails
+async fn read_state(
+    options: &ChunkStateOptions,
+    start_offset: usize,
+    end_offset: usize,
+    quiet: bool,
+) -> anyhow::Result<Option<ChunkState>> {
+    let state_file = &options.state_file;
+    let state = match tokio::fs::read(state_file).await {
+        Ok(state) => state,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+            if !quiet {
+                eprintln!(
+                    "No chunk state file {}, starting from the beginning",
+                    state_file.display()
+                );
+            }
+            return Ok(None);
+        }
+        Err(err) => return Err(err.into()),
+    };
+    let state: ChunkState = serde_json::from_slice(&state)
+        .with_context(|| format!("Invalid chunk state file {}", state_file.display()))?;
+    if (state.start_offset, state.end_offset) != (start_offset, end_offset)
+        || !(start_offset..=end_offset + 1).contains(&state.chunk_offset)
+    {
+        return Err(anyhow::anyhow!(
+            "Chunk state file {} does not belong to transaction data at offsets {start_offset}..={end_offset}",
+            state_file.display()
+        ));
+    }
+    Ok(Some(state))
+}
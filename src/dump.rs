@@ -0,0 +1,409 @@
+use arweave_rs::crypto::base64::Base64;
+use futures_util::{pin_mut, TryStreamExt};
+use serde_json::Value;
+use tokio::io::{AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio_util::io::StreamReader;
+
+use crate::{
+    arweave::Client,
+    async_json::ArrayWriter,
+    avro,
+    bundle::{self, BundleStreamOptions, DataItem},
+    error,
+    output::{FORMAT_VERSION, FORMAT_VERSION_KEY},
+    sink::ITEMS_KEY,
+};
+
+/// Parses ANS-104 bundle from `reader` and writes its DataItems as JSON array into `writer`.
+/// `transform` is applied to every DataItem before it is written, e.g. to redact data or drop tags.
+/// Returns number of written DataItems.
+pub async fn dump_bundle_json<R, W, F>(
+    reader: R,
+    writer: W,
+    options: BundleStreamOptions,
+    mut transform: F,
+) -> anyhow::Result<usize>
+where
+    R: AsyncRead + Unpin,
+    W: AsyncWrite + Unpin,
+    F: FnMut(&mut DataItem),
+{
+    let data_item_stream = bundle::ans104_bundle_data_item_stream_with_options(reader, options);
+    pin_mut!(data_item_stream);
+
+    let mut json_writer = ArrayWriter::new(writer);
+    json_writer.write_open_bracket().await?;
+
+    let mut count = 0;
+    while let Some(mut data_item) = data_item_stream.try_next().await? {
+        transform(&mut data_item);
+        json_writer.write_item(&data_item).await?;
+        count += 1;
+    }
+
+    json_writer.write_close_bracket().await?;
+    Ok(count)
+}
+
+// one data chunk, so that small parser reads do not go through StreamReader one by one
+const READ_BUFFER: usize = 256 * 1024;
+
+/// Fetches data of bundle transaction `id` chunk by chunk and writes its DataItems as JSON array
+/// into `writer`, e.g. socket, pipe or in-memory buffer. `writer` is flushed at the end.
+/// Returns number of written DataItems.
+pub async fn dump_bundle_to_writer<W>(
+    client: &Client,
+    id: &Base64,
+    mut writer: W,
+    options: BundleStreamOptions,
+) -> anyhow::Result<usize>
+where
+    W: AsyncWrite + Unpin,
+{
+    let chunk_stream = client
+        .transaction_data_chunk_stream(id)
+        .map_err(error::into_io_error);
+    let reader = BufReader::with_capacity(READ_BUFFER, StreamReader::new(chunk_stream));
+    pin_mut!(reader);
+
+    let count = dump_bundle_json(reader, &mut writer, options, |_| {}).await?;
+    writer.flush().await?;
+    Ok(count)
+}
+
+/// Result of [`validate_dump_json`].
+#[derive(Debug, Default)]
+pub struct DumpReport {
+    /// number of DataItems in the dump, valid or not
+    pub item_count: usize,
+    /// DataItems whose signature could not be checked, e.g. with spilled data
+    pub unverified_count: usize,
+    /// position of invalid DataItem in the dump with description of the problem
+    pub errors: Vec<(usize, String)>,
+}
+
+/// Checks that JSON dump, bare array or object with header and [`ITEMS_KEY`] array,
+/// deserializes into DataItems. Leading `// _format_version: N` comment line is skipped.
+/// With `verify` DataItem ids are recomputed from signatures and signatures are checked over
+/// tags encoded again, see [`avro::encode_tag_list`].
+pub fn validate_dump_json(dump: &[u8], verify: bool) -> anyhow::Result<DumpReport> {
+    let dump = match dump.strip_prefix(b"//") {
+        Some(comment) => comment
+            .iter()
+            .position(|byte| *byte == b'\n')
+            .map_or(&[][..], |end| &comment[end..]),
+        None => dump,
+    };
+    let items = match serde_json::from_slice(dump)? {
+        Value::Array(items) => items,
+        Value::Object(mut object) => {
+            let version = object.get(FORMAT_VERSION_KEY).and_then(Value::as_u64);
+            if let Some(version) = version.filter(|version| *version > u64::from(FORMAT_VERSION)) {
+                return Err(anyhow::anyhow!(
+                    "Dump format version {version} is newer than supported {FORMAT_VERSION}"
+                ));
+            }
+            match object.remove(ITEMS_KEY) {
+                Some(Value::Array(items)) => items,
+                _ => return Err(anyhow::anyhow!("Dump object has no {ITEMS_KEY} array")),
+            }
+        }
+        _ => return Err(anyhow::anyhow!("Dump is neither array nor object")),
+    };
+
+    let mut report = DumpReport {
+        item_count: items.len(),
+        ..Default::default()
+    };
+    for (index, item) in items.into_iter().enumerate() {
+        let mut data_item: DataItem = match serde_json::from_value(item) {
+            Ok(data_item) => data_item,
+            Err(err) => {
+                report.errors.push((index, err.to_string()));
+                continue;
+            }
+        };
+        if !verify {
+            continue;
+        }
+        if !data_item.verify_id() {
+            report.errors.push((
+                index,
+                format!("[{}] id does not match signature", data_item.bundle_id),
+            ));
+            continue;
+        }
+        data_item.raw_tags = avro::encode_tag_list(&data_item.tags);
+        match data_item
+            .verify_ed25519()
+            .or_else(|| data_item.verify_rsa())
+        {
+            Some(true) => {}
+            Some(false) => report.errors.push((
+                index,
+                format!(
+                    "[{}] {} signature is invalid",
+                    data_item.bundle_id, data_item.signature_name
+                ),
+            )),
+            None => report.unverified_count += 1,
+        }
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod test {
+    use wiremock::{
+        matchers::{method, path},
+        Mock, MockServer, ResponseTemplate,
+    };
+
+    use super::*;
+    use crate::{
+        async_json::JsonFormat,
+        output::OutputOptions,
+        sink::{JsonArraySink, OutputSink},
+    };
+
+    const GOLDEN_PATH: &str = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/res/uYpAeGCj8Xe_J0sKiZ_aJ4Zl1zQLgDH5ia-pqtNLJEA_golden.json"
+    );
+
+    // guards JSON output format, `BLESS=1 cargo test` regenerates golden file after intended changes
+    #[tokio::test]
+    async fn test_dump_matches_golden_output() {
+        let hex_str = include_str!("../res/uYpAeGCj8Xe_J0sKiZ_aJ4Zl1zQLgDH5ia-pqtNLJEA_data.hex");
+        let data = hex::decode(hex_str).expect("should parse");
+
+        let mut output = vec![];
+        dump_bundle_json(
+            data.as_slice(),
+            &mut output,
+            BundleStreamOptions::default(),
+            |_| {},
+        )
+        .await
+        .expect("should work");
+
+        if std::env::var_os("BLESS").is_some() {
+            std::fs::write(GOLDEN_PATH, &output).expect("should write golden file");
+            return;
+        }
+        let golden = std::fs::read(GOLDEN_PATH).expect("should read golden file");
+        assert!(
+            output == golden,
+            "output differs from {GOLDEN_PATH}, run with BLESS=1 to regenerate it if change is intended"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_dump_empty_bundle() {
+        let data = hex::decode(include_str!("../res/empty_bundle.hex")).expect("should parse");
+
+        let mut output = vec![];
+        let count = dump_bundle_json(
+            data.as_slice(),
+            &mut output,
+            BundleStreamOptions::default(),
+            |_| {},
+        )
+        .await
+        .expect("should work");
+        assert_eq!(count, 0);
+        let data_items: Vec<DataItem> = serde_json::from_slice(&output).expect("should parse");
+        assert!(data_items.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_dump_with_transformation() {
+        let hex_str = include_str!("../res/uYpAeGCj8Xe_J0sKiZ_aJ4Zl1zQLgDH5ia-pqtNLJEA_data.hex");
+        let data = hex::decode(hex_str).expect("should parse");
+
+        let mut output = vec![];
+        let count = dump_bundle_json(
+            data.as_slice(),
+            &mut output,
+            BundleStreamOptions::default(),
+            |data_item| {
+                data_item.data.0.clear();
+                data_item.tags.retain(|tag| tag.name == "Content-Type");
+            },
+        )
+        .await
+        .expect("should work");
+        assert_eq!(count, 4);
+
+        let data_items: Vec<DataItem> = serde_json::from_slice(&output).expect("should parse");
+        assert_eq!(data_items.len(), 4);
+        assert!(data_items.iter().all(|item| item.data.is_empty()));
+        assert!(data_items
+            .iter()
+            .all(|item| item.tags.iter().all(|tag| tag.name == "Content-Type")));
+    }
+
+    const FIXTURE_ID: &str = "uYpAeGCj8Xe_J0sKiZ_aJ4Zl1zQLgDH5ia-pqtNLJEA";
+
+    // serves fixture bundle metadata and its data as chunks of given size, starting at given weave offset
+    async fn fixture_gateway(start_offset: usize, chunk_size: usize) -> MockServer {
+        let hex_str = include_str!("../res/uYpAeGCj8Xe_J0sKiZ_aJ4Zl1zQLgDH5ia-pqtNLJEA_data.hex");
+        let data = hex::decode(hex_str).expect("should parse");
+        let tag = |name: &str, value: &str| {
+            serde_json::json!({
+                "name": Base64(name.as_bytes().to_vec()).to_string(),
+                "value": Base64(value.as_bytes().to_vec()).to_string(),
+            })
+        };
+
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path(format!("/tx/{FIXTURE_ID}")))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "format": 2,
+                "id": FIXTURE_ID,
+                "last_tx": "",
+                "owner": "",
+                "tags": [
+                    tag("Bundle-Format", "binary"),
+                    tag("Bundle-Version", "2.0.0"),
+                    tag("App-Name", "fixture"),
+                ],
+                "target": "",
+                "quantity": "0",
+                "data_root": "",
+                "data": "",
+                "data_size": data.len().to_string(),
+                "reward": "0",
+                "signature": "",
+            })))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path(format!("/tx/{FIXTURE_ID}/offset")))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "size": data.len().to_string(),
+                "offset": (start_offset + data.len() - 1).to_string(),
+            })))
+            .mount(&mock_server)
+            .await;
+        for (idx, chunk) in data.chunks(chunk_size).enumerate() {
+            Mock::given(method("GET"))
+                .and(path(format!("/chunk/{}", start_offset + idx * chunk_size)))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                    "chunk": Base64(chunk.to_vec()).to_string(),
+                })))
+                .mount(&mock_server)
+                .await;
+        }
+        mock_server
+    }
+
+    async fn dump_from(mock_server: &MockServer) -> Vec<u8> {
+        let client = Client::new(&mock_server.uri()).expect("should not fail");
+        let mut output = vec![];
+        let count = dump_bundle_to_writer(
+            &client,
+            &FIXTURE_ID.parse().expect("should parse"),
+            &mut output,
+            BundleStreamOptions::default(),
+        )
+        .await
+        .expect("should work");
+        assert_eq!(count, 4);
+        output
+    }
+
+    #[tokio::test]
+    async fn test_dump_bundle_to_writer() {
+        let mock_server = fixture_gateway(1000, 256 * 1024).await;
+        let output = dump_from(&mock_server).await;
+        let golden = std::fs::read(GOLDEN_PATH).expect("should read golden file");
+        assert!(output == golden, "output differs from {GOLDEN_PATH}");
+    }
+
+    // the same transaction served by gateways chunking it differently is dumped byte for byte the same,
+    // also with formatted output where JSON objects are rebuilt, wrapped with bundle tags
+    #[tokio::test]
+    async fn test_dumps_from_different_gateways_are_identical() {
+        let gateways = [
+            fixture_gateway(1000, 256 * 1024).await,
+            fixture_gateway(5_000_000, 1000).await,
+        ];
+        assert_eq!(dump_from(&gateways[0]).await, dump_from(&gateways[1]).await);
+
+        let options = OutputOptions {
+            inline_json_max_size: Some(1024),
+            text_anchor: true,
+            flatten_tags_prefix: Some("App-".to_string()),
+            with_offsets: true,
+            ..Default::default()
+        };
+        let id = FIXTURE_ID.parse().expect("should parse");
+        let mut outputs = vec![];
+        for gateway in &gateways {
+            let client = Client::new(&gateway.uri()).expect("should not fail");
+            let tx = client.fetch_transaction(&id).await.expect("should fetch");
+            let mut header = serde_json::Map::new();
+            let bundle_tags = serde_json::to_value(tx.tag_map()).expect("should serialize");
+            header.insert("bundle_tags".into(), bundle_tags);
+            let mut sink =
+                JsonArraySink::new(vec![], JsonFormat::Pretty, options.clone()).with_header(header);
+            sink.open().await.expect("should not fail");
+            let data_items: Vec<_> = client
+                .dump_bundle_stream(&id)
+                .try_collect()
+                .await
+                .expect("should work");
+            for data_item in &data_items {
+                sink.write_item(data_item).await.expect("should not fail");
+            }
+            sink.finish().await.expect("should not fail");
+            outputs.push(std::mem::take(sink.get_mut()));
+        }
+        assert_eq!(outputs[0], outputs[1]);
+
+        let output: Value = serde_json::from_slice(&outputs[0]).expect("should parse");
+        assert_eq!(output["bundle_tags"]["App-Name"], "fixture");
+        assert_eq!(output[ITEMS_KEY].as_array().map(Vec::len), Some(4));
+    }
+
+    #[tokio::test]
+    async fn test_validate_dump() {
+        let hex_str = include_str!("../res/uYpAeGCj8Xe_J0sKiZ_aJ4Zl1zQLgDH5ia-pqtNLJEA_data.hex");
+        let data = hex::decode(hex_str).expect("should parse");
+
+        let mut output = vec![];
+        dump_bundle_json(
+            data.as_slice(),
+            &mut output,
+            BundleStreamOptions::default(),
+            |_| {},
+        )
+        .await
+        .expect("should work");
+        let report = validate_dump_json(&output, true).expect("should validate");
+        assert_eq!(report.item_count, 4);
+        assert_eq!(report.unverified_count, 0);
+        assert!(report.errors.is_empty(), "{:?}", report.errors);
+
+        let mut items: Vec<Value> = serde_json::from_slice(&output).expect("should parse");
+        items[1]["tags"][0]["value"] = Value::from("changed");
+        items[2]["signature_name"] = Value::from(1);
+        let mut dump = b"// _format_version: 1\n".to_vec();
+        dump.extend(serde_json::to_vec(&items).expect("should serialize"));
+        let report = validate_dump_json(&dump, false).expect("should validate");
+        assert_eq!(report.item_count, 4);
+        assert_eq!(report.errors.len(), 1);
+        assert_eq!(report.errors[0].0, 2);
+
+        let dump = serde_json::json!({FORMAT_VERSION_KEY: FORMAT_VERSION, ITEMS_KEY: items});
+        let dump = serde_json::to_vec(&dump).expect("should serialize");
+        let report = validate_dump_json(&dump, true).expect("should validate");
+        let positions: Vec<_> = report.errors.iter().map(|(index, _)| *index).collect();
+        assert_eq!(positions, [1, 2]);
+        assert!(report.errors[0].1.contains("signature is invalid"));
+
+        validate_dump_json(b"[{}", false).expect_err("should not parse");
+    }
+}
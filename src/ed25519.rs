@@ -0,0 +1,100 @@
+use ed25519_dalek::{Signature, VerifyingKey};
+
+pub const PUBLIC_KEY_LENGTH: usize = ed25519_dalek::PUBLIC_KEY_LENGTH;
+pub const SIGNATURE_LENGTH: usize = ed25519_dalek::SIGNATURE_LENGTH;
+
+/// Verifies ed25519 signature of message, see <https://www.rfc-editor.org/rfc/rfc8032#section-5.1.7>
+/// Strict verification rejects weak public keys and non-canonical signatures.
+/// Malformed public key or signature is reported as invalid signature.
+pub fn verify(public_key: &[u8], message: &[u8], signature: &[u8]) -> bool {
+    let (Ok(public_key), Ok(signature)) = (
+        <&[u8; PUBLIC_KEY_LENGTH]>::try_from(public_key),
+        Signature::from_slice(signature),
+    ) else {
+        return false;
+    };
+    VerifyingKey::from_bytes(public_key)
+        .is_ok_and(|public_key| public_key.verify_strict(message, &signature).is_ok())
+}
+
+// signing is only needed to produce DataItems for tests
+#[cfg(test)]
+pub(crate) mod test {
+    use ed25519_dalek::{Signer, SigningKey};
+
+    use super::*;
+
+    /// Public key and signature of message for 32 bytes secret key.
+    pub(crate) fn sign(secret_key: &[u8; 32], message: &[u8]) -> ([u8; 32], [u8; 64]) {
+        let signing_key = SigningKey::from_bytes(secret_key);
+        (
+            signing_key.verifying_key().to_bytes(),
+            signing_key.sign(message).to_bytes(),
+        )
+    }
+
+    // test vectors from https://www.rfc-editor.org/rfc/rfc8032#section-7.1
+    const VECTORS: [(&str, &str, &str, &str); 2] = [
+        (
+            "9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60",
+            "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a",
+            "",
+            "e5564300c360ac729086e2cc806e828a84877f1eb8e5d974d873e065224901555fb8821590a33bacc61e39701cf9b46bd25bf5f0595bbe24655141438e7a100b",
+        ),
+        (
+            "4ccd089b28ff96da9db6c346ec114e0f5b8a319f35aba624da8cf6ed4fb8a6fb",
+            "3d4017c3e843895a92b70aa74d1b7ebc9c982ccf2ec4968cc0cd55f12af4660c",
+            "72",
+            "92a009a9f0d4cab8720e820b5f642540a2b27b5416503f8fb3762223ebdb69da085ac1e43e15996e458f3613d0f11d8c387b2eaeb4302aeeb00d291612bb0c00",
+        ),
+    ];
+
+    #[test]
+    fn test_verify_known_vectors() {
+        for (_, public_key, message, signature) in VECTORS {
+            let public_key = hex::decode(public_key).expect("should decode");
+            let message = hex::decode(message).expect("should decode");
+            let signature = hex::decode(signature).expect("should decode");
+            assert!(verify(&public_key, &message, &signature));
+
+            assert!(!verify(&public_key, b"other message", &signature));
+            let mut tampered = signature.clone();
+            tampered[40] ^= 1;
+            assert!(!verify(&public_key, &message, &tampered));
+            assert!(!verify(&public_key, &message, &signature[..63]));
+            assert!(!verify(&public_key[..31], &message, &signature));
+        }
+    }
+
+    #[test]
+    fn test_sign_known_vectors() {
+        for (secret_key, public_key, message, signature) in VECTORS {
+            let secret_key: [u8; 32] = hex::decode(secret_key)
+                .expect("should decode")
+                .try_into()
+                .expect("should be 32 bytes");
+            let message = hex::decode(message).expect("should decode");
+            let (signed_key, signed) = sign(&secret_key, &message);
+            assert_eq!(hex::encode(signed_key), public_key);
+            assert_eq!(hex::encode(signed), signature);
+        }
+    }
+
+    #[test]
+    fn test_verify_rejects_non_canonical_s() {
+        let (_, public_key, message, signature) = VECTORS[0];
+        let public_key = hex::decode(public_key).expect("should decode");
+        let message = hex::decode(message).expect("should decode");
+        let mut signature = hex::decode(signature).expect("should decode");
+        // S + L verifies the same equation but is not accepted, L is the group order
+        let order = hex::decode("edd3f55c1a631258d69cf7a2def9de1400000000000000000000000000000010")
+            .expect("should decode");
+        let mut carry = 0u16;
+        for (s, l) in signature[32..].iter_mut().zip(order) {
+            let sum = u16::from(*s) + u16::from(l) + carry;
+            *s = sum as u8;
+            carry = sum >> 8;
+        }
+        assert!(!verify(&public_key, &message, &signature));
+    }
+}
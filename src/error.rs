@@ -0,0 +1,207 @@
+use std::time::Duration;
+
+use serde::Serialize;
+
+/// Errors which callers may want to react to programmatically.
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("Given transaction {transaction_id} is not ANS-104 bundle")]
+    NotABundle { transaction_id: String },
+    #[error("Transaction is pending")]
+    Pending,
+    #[error("Transaction data size {size} exceeds maximum size {max_size}")]
+    TooLarge { size: usize, max_size: usize },
+    #[error("Bundle parse failed")]
+    Parse,
+    #[error("Unexpected content type {content_type} for url {url}, expected JSON")]
+    UnexpectedContentType { url: String, content_type: String },
+    #[error("Chunk at offset {offset} is not yet available")]
+    ChunkPending { offset: usize },
+    #[error("Transaction data is not fully seeded, retrieved {retrieved} of {size} bytes")]
+    BundleNotFullySeeded { retrieved: usize, size: usize },
+    #[error("Dump did not finish within deadline of {}", humantime::format_duration(*deadline))]
+    DeadlineExceeded { deadline: Duration },
+    #[error("Dump was interrupted")]
+    Interrupted,
+    #[error("HTTP status {status} for url {url}{}", trace_id.as_ref().map(|id| format!(" (trace id: {id})")).unwrap_or_default())]
+    Http {
+        status: u16,
+        url: String,
+        /// delay requested by gateway in `Retry-After` header
+        retry_after: Option<Duration>,
+        /// gateway request id from `x-trace-id` header
+        trace_id: Option<String>,
+    },
+}
+
+/// Converts error of transaction data stream for `StreamReader`, keeping typed [`Error`]
+/// reachable from io error so that [`ErrorKind::of`] still recognizes it.
+pub fn into_io_error(err: anyhow::Error) -> std::io::Error {
+    match err.downcast::<Error>() {
+        Ok(err) => std::io::Error::other(err),
+        Err(err) => std::io::Error::other(err),
+    }
+}
+
+/// Stable error category used for machine-readable error output and exit codes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorKind {
+    Other,
+    NotABundle,
+    Pending,
+    TooLarge,
+    Http,
+    Network,
+    Parse,
+    Io,
+    NotFullySeeded,
+    Timeout,
+    Interrupted,
+}
+
+impl ErrorKind {
+    pub fn of(err: &anyhow::Error) -> Self {
+        // errors of transaction data stream reach the parser wrapped into io errors,
+        // cause of failed read is more telling than parse failure itself
+        let stream_error = err
+            .chain()
+            .filter_map(|cause| cause.downcast_ref::<std::io::Error>())
+            .find_map(|err| err.get_ref()?.downcast_ref::<Error>());
+        // typed error wins (also as context) - parse errors may carry io errors as their cause
+        let typed_error = match err.downcast_ref::<Error>() {
+            Some(Error::Parse) => stream_error.or(Some(&Error::Parse)),
+            typed_error => typed_error,
+        };
+        if let Some(err) = typed_error {
+            return match err {
+                Error::NotABundle { .. } => Self::NotABundle,
+                Error::Pending | Error::ChunkPending { .. } => Self::Pending,
+                Error::TooLarge { .. } => Self::TooLarge,
+                Error::Parse => Self::Parse,
+                Error::Http { .. } | Error::UnexpectedContentType { .. } => Self::Http,
+                Error::BundleNotFullySeeded { .. } => Self::NotFullySeeded,
+                Error::DeadlineExceeded { .. } => Self::Timeout,
+                Error::Interrupted => Self::Interrupted,
+            };
+        }
+        for cause in err.chain() {
+            if let Some(err) = cause.downcast_ref::<reqwest::Error>() {
+                return if err.status().is_some() {
+                    Self::Http
+                } else {
+                    Self::Network
+                };
+            }
+            if cause.is::<std::io::Error>() {
+                return Self::Io;
+            }
+        }
+        Self::Other
+    }
+
+    pub fn exit_code(self) -> u8 {
+        match self {
+            Self::Other => 1,
+            Self::NotABundle => 3,
+            Self::TooLarge => 4,
+            Self::Pending => 5,
+            Self::Http => 6,
+            Self::Network => 7,
+            Self::Parse => 8,
+            Self::Io => 9,
+            Self::NotFullySeeded => 10,
+            Self::Timeout => 11,
+            // as if killed by SIGINT
+            Self::Interrupted => 130,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct ErrorReport {
+    pub error: ErrorKind,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub transaction_id: Option<String>,
+}
+
+impl ErrorReport {
+    pub fn new(err: &anyhow::Error, transaction_id: Option<String>) -> Self {
+        Self {
+            error: ErrorKind::of(err),
+            message: format!("{err:#}"),
+            transaction_id,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use anyhow::Context;
+
+    use super::*;
+
+    #[test]
+    fn test_kind_of_typed_error() {
+        let err = anyhow::Error::new(Error::NotABundle {
+            transaction_id: "abc".to_string(),
+        });
+        assert_eq!(ErrorKind::of(&err), ErrorKind::NotABundle);
+    }
+
+    #[test]
+    fn test_kind_of_error_passed_through_reader() {
+        let stream_error = anyhow::Error::new(Error::BundleNotFullySeeded {
+            retrieved: 10,
+            size: 20,
+        });
+        let err = Err::<(), _>(into_io_error(stream_error))
+            .context("DataItem read")
+            .context(Error::Parse)
+            .expect_err("should fail");
+        assert_eq!(ErrorKind::of(&err), ErrorKind::NotFullySeeded);
+    }
+
+    #[test]
+    fn test_kind_of_cancellation() {
+        let err = anyhow::Error::new(Error::DeadlineExceeded {
+            deadline: Duration::from_secs(300),
+        });
+        assert_eq!(err.to_string(), "Dump did not finish within deadline of 5m");
+        assert_eq!(ErrorKind::of(&err).exit_code(), 11);
+
+        let err = anyhow::Error::new(Error::Interrupted);
+        assert_eq!(ErrorKind::of(&err).exit_code(), 130);
+    }
+
+    #[test]
+    fn test_kind_of_context_wrapped_error() {
+        let err = Err::<(), _>(std::io::Error::from(std::io::ErrorKind::UnexpectedEof))
+            .context("signature")
+            .context(Error::Parse)
+            .expect_err("should fail");
+        assert_eq!(ErrorKind::of(&err), ErrorKind::Parse);
+
+        let err = Err::<(), _>(std::io::Error::from(std::io::ErrorKind::NotFound))
+            .context("output file")
+            .expect_err("should fail");
+        assert_eq!(ErrorKind::of(&err), ErrorKind::Io);
+    }
+
+    #[test]
+    fn test_error_report_json() {
+        let err = anyhow::Error::new(Error::NotABundle {
+            transaction_id: "abc".to_string(),
+        });
+        let report = ErrorReport::new(&err, Some("abc".to_string()));
+        assert_eq!(
+            serde_json::to_value(&report).expect("should serialize"),
+            serde_json::json!({
+                "error": "not_a_bundle",
+                "message": "Given transaction abc is not ANS-104 bundle",
+                "transaction_id": "abc",
+            })
+        );
+    }
+}
@@ -0,0 +1,203 @@
+use arweave_rs::crypto::base64::Base64;
+use serde::{Deserialize, Serialize};
+
+const TRANSACTIONS_QUERY: &str = r#"
+query($ids: [ID!], $owners: [String!], $tags: [TagFilter!], $block: BlockFilter, $first: Int, $after: String, $sort: SortOrder) {
+  transactions(ids: $ids, owners: $owners, tags: $tags, block: $block, first: $first, after: $after, sort: $sort) {
+    pageInfo { hasNextPage }
+    edges { cursor node { id } }
+  }
+}
+"#;
+
+pub const MAX_PAGE_SIZE: usize = 100;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TagFilter {
+    pub name: String,
+    pub values: Vec<String>,
+}
+
+impl TagFilter {
+    pub fn bundles() -> Self {
+        Self {
+            name: "Bundle-Format".to_string(),
+            values: vec!["binary".to_string()],
+        }
+    }
+}
+
+/// Block heights transactions were mined in, both ends inclusive. Pending transactions never match.
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct BlockFilter {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum SortOrder {
+    HeightAsc,
+    HeightDesc,
+}
+
+/// Variables of the gateway `transactions` GraphQL query.
+#[derive(Debug, Clone, Serialize)]
+pub struct TransactionQuery {
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub ids: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub owners: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<TagFilter>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub block: Option<BlockFilter>,
+    pub first: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub after: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sort: Option<SortOrder>,
+}
+
+impl Default for TransactionQuery {
+    fn default() -> Self {
+        Self {
+            ids: vec![],
+            owners: vec![],
+            tags: vec![],
+            block: None,
+            first: MAX_PAGE_SIZE,
+            after: None,
+            sort: None,
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub(crate) struct GraphQlRequest<'a> {
+    query: &'static str,
+    variables: &'a TransactionQuery,
+}
+
+impl<'a> GraphQlRequest<'a> {
+    pub(crate) fn transactions(variables: &'a TransactionQuery) -> Self {
+        Self {
+            query: TRANSACTIONS_QUERY,
+            variables,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct GraphQlResponse {
+    pub data: Option<TransactionsData>,
+    #[serde(default)]
+    pub errors: Vec<GraphQlError>,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct GraphQlError {
+    pub message: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct TransactionsData {
+    pub transactions: TransactionPage,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TransactionPage {
+    #[serde(rename = "pageInfo")]
+    pub page_info: PageInfo,
+    pub edges: Vec<TransactionEdge>,
+}
+
+impl TransactionPage {
+    pub fn last_cursor(&self) -> Option<&str> {
+        self.edges.last().map(|edge| edge.cursor.as_str())
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PageInfo {
+    #[serde(rename = "hasNextPage")]
+    pub has_next_page: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TransactionEdge {
+    pub cursor: String,
+    pub node: TransactionNode,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TransactionNode {
+    pub id: Base64,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_query_variables_skip_empty_filters() {
+        let query = TransactionQuery {
+            tags: vec![TagFilter::bundles()],
+            ..Default::default()
+        };
+        let request =
+            serde_json::to_value(GraphQlRequest::transactions(&query)).expect("should serialize");
+
+        assert_eq!(
+            request["variables"],
+            serde_json::json!({
+                "tags": [{ "name": "Bundle-Format", "values": ["binary"] }],
+                "first": 100,
+            })
+        );
+    }
+
+    #[test]
+    fn test_sort_order_serialization() {
+        let query = TransactionQuery {
+            sort: Some(SortOrder::HeightDesc),
+            ..Default::default()
+        };
+        let variables = serde_json::to_value(&query).expect("should serialize");
+        assert_eq!(variables["sort"], "HEIGHT_DESC");
+    }
+
+    #[test]
+    fn test_block_filter_serialization() {
+        let query = TransactionQuery {
+            block: Some(BlockFilter {
+                min: Some(1000),
+                max: None,
+            }),
+            ..Default::default()
+        };
+        let variables = serde_json::to_value(&query).expect("should serialize");
+        assert_eq!(variables["block"], serde_json::json!({ "min": 1000 }));
+    }
+
+    #[test]
+    fn test_parse_transactions_response() {
+        let response: GraphQlResponse = serde_json::from_str(
+            r#"{"data":{"transactions":{
+                "pageInfo":{"hasNextPage":true},
+                "edges":[{"cursor":"abc","node":{"id":"uYpAeGCj8Xe_J0sKiZ_aJ4Zl1zQLgDH5ia-pqtNLJEA"}}]
+            }}}"#,
+        )
+        .expect("should parse");
+
+        let page = response.data.expect("should have data").transactions;
+        assert!(page.page_info.has_next_page);
+        assert_eq!(page.last_cursor(), Some("abc"));
+        assert_eq!(
+            page.edges[0].node.id.to_string(),
+            "uYpAeGCj8Xe_J0sKiZ_aJ4Zl1zQLgDH5ia-pqtNLJEA"
+        );
+    }
+}
@@ -0,0 +1,150 @@
+use std::{collections::HashSet, fmt, str::FromStr};
+
+use arweave_rs::crypto::base64::Base64;
+use serde::{
+    de::{MapAccess, Visitor},
+    Deserialize, Deserializer,
+};
+
+/// Tag of index transaction naming one child bundle transaction, can repeat.
+pub const DEFAULT_INDEX_TAG: &str = "Bundle-Ref";
+pub const MANIFEST_CONTENT_TYPE: &str = "application/x.arweave-manifest+json";
+
+/// Child bundle transactions referenced by an index transaction, in order of appearance.
+#[derive(Debug, Default, PartialEq)]
+pub struct IndexManifest {
+    pub ids: Vec<Base64>,
+    // raw bytes of `ids`, which are not hashable
+    seen: HashSet<Vec<u8>>,
+}
+
+#[derive(Deserialize)]
+struct PathManifest {
+    #[serde(default, deserialize_with = "paths_in_order")]
+    paths: Vec<(String, PathEntry)>,
+}
+
+#[derive(Deserialize)]
+struct PathEntry {
+    id: String,
+}
+
+// JSON object entries as they appear, serde_json map would sort them by path
+fn paths_in_order<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<(String, PathEntry)>, D::Error> {
+    struct PathsVisitor;
+
+    impl<'de> Visitor<'de> for PathsVisitor {
+        type Value = Vec<(String, PathEntry)>;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            formatter.write_str("object of paths")
+        }
+
+        fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+            let mut paths = vec![];
+            while let Some(entry) = map.next_entry()? {
+                paths.push(entry);
+            }
+            Ok(paths)
+        }
+    }
+
+    deserializer.deserialize_map(PathsVisitor)
+}
+
+impl IndexManifest {
+    /// Collects values of all `tag_name` tags.
+    pub fn from_tags<'a>(
+        tags: impl IntoIterator<Item = (&'a str, &'a str)>,
+        tag_name: &str,
+    ) -> anyhow::Result<Self> {
+        let mut manifest = Self::default();
+        for (name, value) in tags {
+            if name == tag_name {
+                manifest.push(value)?;
+            }
+        }
+        Ok(manifest)
+    }
+
+    /// Collects ids of all paths of an Arweave path manifest,
+    /// see <https://github.com/ArweaveTeam/arweave/wiki/Path-Manifests>
+    pub fn from_path_manifest(data: &[u8]) -> anyhow::Result<Self> {
+        let path_manifest: PathManifest = serde_json::from_slice(data)?;
+        let mut manifest = Self::default();
+        for (_, entry) in &path_manifest.paths {
+            manifest.push(&entry.id)?;
+        }
+        Ok(manifest)
+    }
+
+    // same child can be referenced more than once, it is dumped only once
+    fn push(&mut self, id: &str) -> anyhow::Result<()> {
+        let id = Base64::from_str(id)
+            .map_err(|err| anyhow::anyhow!("Invalid child transaction ID {id}: {err}"))?;
+        if self.seen.insert(id.0.clone()) {
+            self.ids.push(id);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const FIRST: &str = "uYpAeGCj8Xe_J0sKiZ_aJ4Zl1zQLgDH5ia-pqtNLJEA";
+    const SECOND: &str = "o0le1MwgKBVIrh3fqJnWCGNa4N0rDd2WDm15jjGIvBo";
+
+    #[test]
+    fn test_manifest_from_tags() {
+        let tags = [
+            ("App-Name", "uploader"),
+            (DEFAULT_INDEX_TAG, SECOND),
+            (DEFAULT_INDEX_TAG, FIRST),
+            (DEFAULT_INDEX_TAG, SECOND),
+        ];
+
+        let manifest = IndexManifest::from_tags(tags, DEFAULT_INDEX_TAG).expect("should parse");
+        assert_eq!(
+            manifest.ids,
+            vec![
+                Base64::from_str(SECOND).expect("should parse"),
+                Base64::from_str(FIRST).expect("should parse"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_manifest_from_tags_with_invalid_id() {
+        IndexManifest::from_tags([(DEFAULT_INDEX_TAG, "not base64!")], DEFAULT_INDEX_TAG)
+            .expect_err("should fail");
+    }
+
+    #[test]
+    fn test_manifest_from_path_manifest() {
+        // not sorted by path, ids are kept in order of appearance
+        let data = format!(
+            r#"{{
+                "manifest": "arweave/paths",
+                "version": "0.1.0",
+                "paths": {{
+                    "b": {{ "id": "{SECOND}" }},
+                    "a": {{ "id": "{FIRST}" }},
+                    "c": {{ "id": "{SECOND}" }}
+                }}
+            }}"#
+        );
+
+        let manifest = IndexManifest::from_path_manifest(data.as_bytes()).expect("should parse");
+        assert_eq!(
+            manifest.ids,
+            vec![
+                Base64::from_str(SECOND).expect("should parse"),
+                Base64::from_str(FIRST).expect("should parse"),
+            ]
+        );
+    }
+}
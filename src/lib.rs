@@ -1,4 +1,23 @@
 pub mod arweave;
+#[cfg(feature = "csv")]
+pub mod async_csv;
 pub mod async_json;
+pub mod async_tar;
 pub mod avro;
+pub mod budget;
 pub mod bundle;
+pub mod cache;
+pub mod chunk_state;
+pub mod dump;
+pub mod ed25519;
+pub mod error;
+pub mod graphql;
+pub mod index;
+pub mod merkle;
+pub mod metrics;
+pub mod output;
+#[cfg(feature = "s3")]
+pub mod s3;
+pub mod signature;
+pub mod sink;
+pub mod stats;
@@ -0,0 +1,165 @@
+use arweave_rs::crypto::hash::{hash_all_sha256, sha256};
+
+const HASH_SIZE: usize = 32;
+const NOTE_SIZE: usize = 32;
+
+/// Range of transaction data (end exclusive) and hash of its chunk proven by a data path.
+#[derive(Debug, PartialEq, Eq)]
+pub struct ProvenRange {
+    pub start: usize,
+    pub end: usize,
+    pub data_hash: [u8; HASH_SIZE],
+}
+
+/// Walks data path from `data_root` down to the leaf covering `offset`,
+/// see `validatePath` of arweave-js <https://github.com/ArweaveTeam/arweave-js/blob/master/src/common/lib/merkle.ts>
+pub fn validate_path(
+    data_root: &[u8],
+    offset: usize,
+    data_size: usize,
+    data_path: &[u8],
+) -> Option<ProvenRange> {
+    if offset >= data_size {
+        return None;
+    }
+
+    let mut id = data_root;
+    let mut start = 0;
+    let mut end = data_size;
+    let mut path = data_path;
+    // every branch is left id, right id and offset note, leaf is data hash and offset note
+    while path.len() > HASH_SIZE + NOTE_SIZE {
+        if path.len() < 2 * HASH_SIZE + NOTE_SIZE {
+            return None;
+        }
+        let (left, rest) = path.split_at(HASH_SIZE);
+        let (right, rest) = rest.split_at(HASH_SIZE);
+        let (note, rest) = rest.split_at(NOTE_SIZE);
+        if hash_all_sha256(vec![left, right, note]) != id {
+            return None;
+        }
+        let branch_offset = note_to_usize(note)?;
+        if offset < branch_offset {
+            id = left;
+            end = end.min(branch_offset);
+        } else {
+            id = right;
+            start = start.max(branch_offset);
+        }
+        path = rest;
+    }
+
+    if path.len() != HASH_SIZE + NOTE_SIZE {
+        return None;
+    }
+    let (data_hash, note) = path.split_at(HASH_SIZE);
+    if hash_all_sha256(vec![data_hash, note]) != id {
+        return None;
+    }
+    Some(ProvenRange {
+        start,
+        end,
+        data_hash: data_hash.try_into().ok()?,
+    })
+}
+
+/// Checks that chunk starting at `offset` of transaction data is proven by the data path.
+pub fn verify_chunk(
+    data_root: &[u8],
+    offset: usize,
+    data_size: usize,
+    chunk: &[u8],
+    data_path: &[u8],
+) -> bool {
+    validate_path(data_root, offset, data_size, data_path).is_some_and(|range| {
+        range.start == offset
+            && range.end - range.start == chunk.len()
+            && range.data_hash == sha256(chunk)
+    })
+}
+
+// notes are 32 bytes big endian integers
+fn note_to_usize(note: &[u8]) -> Option<usize> {
+    let (upper, lower) = note.split_at(NOTE_SIZE - 8);
+    if upper.iter().any(|b| *b != 0) {
+        return None;
+    }
+    usize::try_from(u64::from_be_bytes(lower.try_into().ok()?)).ok()
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use arweave_rs::crypto::merkle::{generate_data_root, generate_leaves, resolve_proofs};
+
+    use super::*;
+
+    /// chunk data with its data path
+    pub(crate) type ProvenChunk = (Vec<u8>, Vec<u8>);
+
+    /// Splits data into chunks the same way arweave-rs does, returning data root and chunks with their data paths.
+    pub(crate) fn chunk_data(data: &[u8]) -> (Vec<u8>, Vec<ProvenChunk>) {
+        let leaves = generate_leaves(data.to_vec()).expect("should not fail");
+        let ranges = leaves
+            .iter()
+            .map(|leaf| (leaf.min_byte_range, leaf.max_byte_range))
+            .collect::<Vec<_>>();
+        let root = generate_data_root(leaves).expect("should not fail");
+        let data_root = root.id.to_vec();
+        let proofs = resolve_proofs(root, None).expect("should not fail");
+
+        let chunks = ranges
+            .into_iter()
+            .zip(proofs)
+            .filter(|((start, end), _)| start != end)
+            .map(|((start, end), proof)| (data[start..end].to_vec(), proof.proof))
+            .collect();
+        (data_root, chunks)
+    }
+
+    #[test]
+    fn test_chunks_are_verified_against_data_root() {
+        let data = (0..600_000).map(|i| (i % 251) as u8).collect::<Vec<_>>();
+        let (data_root, chunks) = chunk_data(&data);
+        assert_eq!(chunks.len(), 3);
+
+        let mut offset = 0;
+        for (chunk, data_path) in &chunks {
+            assert!(verify_chunk(
+                &data_root,
+                offset,
+                data.len(),
+                chunk,
+                data_path
+            ));
+            offset += chunk.len();
+        }
+    }
+
+    #[test]
+    fn test_tampered_chunk_is_rejected() {
+        let data = (0..600_000).map(|i| (i % 251) as u8).collect::<Vec<_>>();
+        let (data_root, chunks) = chunk_data(&data);
+        let (chunk, data_path) = &chunks[1];
+        let offset = chunks[0].0.len();
+
+        let mut tampered = chunk.clone();
+        tampered[10] ^= 1;
+        assert!(!verify_chunk(
+            &data_root,
+            offset,
+            data.len(),
+            &tampered,
+            data_path
+        ));
+        // valid chunk, but claimed at wrong offset
+        assert!(!verify_chunk(&data_root, 0, data.len(), chunk, data_path));
+        // truncated path
+        assert!(!verify_chunk(
+            &data_root,
+            offset,
+            data.len(),
+            chunk,
+            &data_path[..data_path.len() - 1]
+        ));
+    }
+}
@@ -0,0 +1,133 @@
+use std::{
+    fmt::Write,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+/// Process wide monotonic counter exported in Prometheus text format.
+pub struct Counter {
+    name: &'static str,
+    help: &'static str,
+    value: AtomicU64,
+}
+
+impl Counter {
+    const fn new(name: &'static str, help: &'static str) -> Self {
+        Self {
+            name,
+            help,
+            value: AtomicU64::new(0),
+        }
+    }
+
+    pub fn inc(&self) {
+        self.add(1);
+    }
+
+    pub fn add(&self, n: u64) {
+        self.value.fetch_add(n, Ordering::Relaxed);
+    }
+
+    pub fn get(&self) -> u64 {
+        self.value.load(Ordering::Relaxed)
+    }
+}
+
+pub static BUNDLES_DUMPED: Counter = Counter::new(
+    "arweave_dumper_bundles_dumped_total",
+    "Bundles fully dumped",
+);
+pub static ITEMS_WRITTEN: Counter = Counter::new(
+    "arweave_dumper_items_written_total",
+    "DataItems written to output",
+);
+pub static BYTES_FETCHED: Counter = Counter::new(
+    "arweave_dumper_bytes_fetched_total",
+    "Transaction data bytes fetched from gateway",
+);
+pub static FETCH_ERRORS: Counter = Counter::new(
+    "arweave_dumper_fetch_errors_total",
+    "Failed gateway requests",
+);
+pub static RETRIES: Counter =
+    Counter::new("arweave_dumper_retries_total", "Retried gateway requests");
+
+static ALL: [&Counter; 5] = [
+    &BUNDLES_DUMPED,
+    &ITEMS_WRITTEN,
+    &BYTES_FETCHED,
+    &FETCH_ERRORS,
+    &RETRIES,
+];
+
+/// Renders all counters in Prometheus text exposition format.
+pub fn render() -> String {
+    let mut out = String::new();
+    for counter in ALL {
+        // writing into String can not fail
+        let _ = writeln!(out, "# HELP {} {}", counter.name, counter.help);
+        let _ = writeln!(out, "# TYPE {} counter", counter.name);
+        let _ = writeln!(out, "{} {}", counter.name, counter.get());
+    }
+    out
+}
+
+/// Answers every HTTP request accepted on the listener with rendered counters.
+#[cfg(feature = "metrics")]
+pub async fn serve(listener: tokio::net::TcpListener) -> anyhow::Result<()> {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    loop {
+        let (mut stream, _) = listener.accept().await?;
+        tokio::spawn(async move {
+            // request itself does not matter, there is only one thing to serve
+            let mut buf = [0u8; 1024];
+            let _ = stream.read(&mut buf).await?;
+            let body = render();
+            let response = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                body.len()
+            );
+            stream.write_all(response.as_bytes()).await?;
+            stream.shutdown().await
+        });
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_render_contains_all_counters() {
+        RETRIES.inc();
+
+        let rendered = render();
+        for counter in ALL {
+            assert!(rendered.contains(&format!("# TYPE {} counter\n", counter.name)));
+        }
+        // counters are process wide and other tests may bump them
+        let retries = rendered
+            .lines()
+            .find_map(|line| line.strip_prefix("arweave_dumper_retries_total "))
+            .expect("should be present");
+        assert!(retries.parse::<u64>().expect("should parse") >= 1);
+    }
+
+    #[cfg(feature = "metrics")]
+    #[tokio::test]
+    async fn test_metrics_are_served_over_http() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("should bind");
+        let addr = listener.local_addr().expect("should have address");
+        tokio::spawn(serve(listener));
+
+        let body = reqwest::get(format!("http://{addr}/metrics"))
+            .await
+            .expect("should not fail")
+            .text()
+            .await
+            .expect("should not fail");
+        assert!(body.contains("arweave_dumper_items_written_total"));
+    }
+}
@@ -0,0 +1,497 @@
+use std::collections::BTreeMap;
+
+use arweave_rs::crypto::base64::Base64;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::{
+    avro::BundleTag,
+    bundle::{DataItem, ItemPosition},
+};
+
+pub const DEFAULT_INLINE_JSON_MAX_SIZE: usize = 1024 * 1024;
+
+/// Version of serialized DataItem shape, bumped whenever fields are renamed, removed or change their meaning.
+pub const FORMAT_VERSION: u32 = 1;
+/// Key of [`FORMAT_VERSION`] in wrapping object or array header comment.
+pub const FORMAT_VERSION_KEY: &str = "_format_version";
+
+/// Options shaping JSON representation of dumped DataItems.
+#[derive(Debug, Default, Clone)]
+pub struct OutputOptions {
+    /// embed `application/json` data up to given size as parsed JSON instead of base64 string
+    pub inline_json_max_size: Option<usize>,
+    /// write anchor as string when it is text, see [`DataItem::anchor_string`]
+    pub text_anchor: bool,
+    /// write only tags whose name starts with given prefix
+    pub only_tags_prefix: Option<String>,
+    /// strip given prefix from tag names having it, e.g. `Custom:Key` becomes `Key` for `Custom:`
+    pub flatten_tags_prefix: Option<String>,
+    /// write index of DataItem in its bundle with offset and length of its bytes, see [`PositionedItem`]
+    pub with_offsets: bool,
+    /// DataItem JSON keys renamed on output, e.g. `bundle_id` to `id`, in given order
+    pub rename_fields: Vec<(String, String)>,
+    /// write only first given number of data bytes, see [`DataPreview`]
+    pub data_preview: Option<usize>,
+}
+
+impl OutputOptions {
+    fn is_default(&self) -> bool {
+        self.inline_json_max_size.is_none()
+            && !self.text_anchor
+            && self.only_tags_prefix.is_none()
+            && self.flatten_tags_prefix.is_none()
+            && !self.with_offsets
+            && self.rename_fields.is_empty()
+            && self.data_preview.is_none()
+    }
+}
+
+/// DataItem as it is written into the output.
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+pub enum OutputItem<'a> {
+    Raw(&'a DataItem),
+    Formatted(Value),
+    Positioned(PositionedItem),
+}
+
+/// DataItem followed by `index`, `offset` and `length` fields of its [`ItemPosition`],
+/// offset is relative to the start of bundle data the DataItem was read from.
+#[derive(Debug, Serialize)]
+pub struct PositionedItem {
+    #[serde(flatten)]
+    pub item: Value,
+    #[serde(flatten)]
+    pub position: ItemPosition,
+}
+
+pub fn format_item<'a>(
+    data_item: &'a DataItem,
+    options: &OutputOptions,
+) -> anyhow::Result<OutputItem<'a>> {
+    if options.with_offsets {
+        let options = OutputOptions {
+            with_offsets: false,
+            ..options.clone()
+        };
+        let item = format_item(data_item, &options)?;
+        // DataItems not read from bundle stream have no position to write
+        return Ok(match data_item.position {
+            Some(position) => OutputItem::Positioned(PositionedItem {
+                item: serde_json::to_value(item)?,
+                position,
+            }),
+            None => item,
+        });
+    }
+    if options.is_default() {
+        return Ok(OutputItem::Raw(data_item));
+    }
+
+    let mut value = serde_json::to_value(data_item)?;
+    if let Some(max_size) = options.inline_json_max_size {
+        if let Some(data) = inline_json(data_item, max_size) {
+            value["data"] = data;
+            value["data_inlined"] = Value::Bool(true);
+        }
+    }
+    if let Some(max_size) = options.data_preview {
+        // inlined JSON is written whole, its size is limited by own option, spilled data is in its file
+        if value.get("data_inlined").is_none() && data_item.data_file.is_none() {
+            let data = &data_item.data.0;
+            value["data_len"] = Value::from(data.len());
+            if data.len() > max_size {
+                value["data"] = serde_json::to_value(DataPreview(&data[..max_size]))?;
+                value["data_truncated"] = Value::Bool(true);
+            }
+        }
+    }
+    if options.text_anchor {
+        if let Some(anchor) = data_item.anchor_string() {
+            value["anchor"] = Value::String(anchor);
+            value["anchor_text"] = Value::Bool(true);
+        }
+    }
+    if let Some(tags) = output_tags(&data_item.tags, options) {
+        // tags as read stay available next to rewritten ones
+        value["original_tags"] = std::mem::replace(&mut value["tags"], serde_json::to_value(tags)?);
+    }
+    rename_fields(&mut value, &options.rename_fields)?;
+    Ok(OutputItem::Formatted(value))
+}
+
+/// Prefix of DataItem data, serialized as base64 string the same way as whole data.
+/// Written with `data_truncated: true` and `data_len` holding size of whole data.
+pub struct DataPreview<'a>(pub &'a [u8]);
+
+impl Serialize for DataPreview<'_> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        Base64(self.0.to_vec()).serialize(serializer)
+    }
+}
+
+// missing fields (e.g. optional ones) are not renamed, renaming over present field fails
+fn rename_fields(value: &mut Value, renames: &[(String, String)]) -> anyhow::Result<()> {
+    let Some(object) = value.as_object_mut() else {
+        return Ok(());
+    };
+    for (from, to) in renames {
+        let Some(field) = object.remove(from) else {
+            continue;
+        };
+        if object.contains_key(to) {
+            return Err(anyhow::anyhow!(
+                "Can not rename field {from} to {to}, DataItem already has {to} field"
+            ));
+        }
+        object.insert(to.clone(), field);
+    }
+    Ok(())
+}
+
+// tags filtered and renamed according to options, `None` if they stay the same
+fn output_tags(tags: &[BundleTag], options: &OutputOptions) -> Option<Vec<BundleTag>> {
+    if options.only_tags_prefix.is_none() && options.flatten_tags_prefix.is_none() {
+        return None;
+    }
+    let output_tags: Vec<_> = tags
+        .iter()
+        .filter(|tag| {
+            options
+                .only_tags_prefix
+                .as_ref()
+                .is_none_or(|prefix| tag.name.starts_with(prefix.as_str()))
+        })
+        .map(|tag| BundleTag {
+            name: options
+                .flatten_tags_prefix
+                .as_ref()
+                .and_then(|prefix| tag.name.strip_prefix(prefix.as_str()))
+                .unwrap_or(&tag.name)
+                .to_string(),
+            value: tag.value.clone(),
+        })
+        .collect();
+    (output_tags != tags).then_some(output_tags)
+}
+
+// falls back to base64 data if DataItem is not JSON, too big or fails to parse
+fn inline_json(data_item: &DataItem, max_size: usize) -> Option<Value> {
+    let is_json = data_item
+        .get_tag("Content-Type")
+        .is_some_and(|content_type| content_type.starts_with("application/json"));
+    if !is_json || data_item.data.0.len() > max_size {
+        return None;
+    }
+    serde_json::from_slice(&data_item.data.0).ok()
+}
+
+/// DataItem id with its tags as name to value object, sorted by name so that output is reproducible.
+/// First value wins for repeated tag names.
+#[derive(Debug, Serialize)]
+pub struct TagsRecord<'a> {
+    pub id: &'a Base64,
+    pub tags: BTreeMap<&'a str, &'a str>,
+}
+
+impl<'a> From<&'a DataItem> for TagsRecord<'a> {
+    fn from(data_item: &'a DataItem) -> Self {
+        let mut tags = BTreeMap::new();
+        for tag in &data_item.tags {
+            tags.entry(tag.name.as_str()).or_insert(tag.value.as_str());
+        }
+        Self {
+            id: &data_item.bundle_id,
+            tags,
+        }
+    }
+}
+
+/// Single tag of a DataItem, every tag in order (repeated ones too) becomes one.
+#[derive(Debug, Serialize)]
+pub struct TagTriple<'a> {
+    pub id: &'a Base64,
+    pub name: &'a str,
+    pub value: &'a str,
+}
+
+impl<'a> TagTriple<'a> {
+    pub fn all(data_item: &'a DataItem) -> impl Iterator<Item = Self> {
+        data_item.tags.iter().map(|tag| Self {
+            id: &data_item.bundle_id,
+            name: &tag.name,
+            value: &tag.value,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn json_data_item(data: &[u8]) -> DataItem {
+        DataItem {
+            signature_name: "ed25519".to_string(),
+            signature_type: 2,
+            signature: Base64::empty(),
+            bundle_id: Base64::empty(),
+            owner_public_key: Base64::empty(),
+            target: None,
+            anchor: None,
+            tags: vec![BundleTag {
+                name: "Content-Type".to_string(),
+                value: "application/json; charset=utf-8".to_string(),
+            }],
+            data: Base64(data.to_vec()),
+            data_file: None,
+            data_sha256: None,
+            raw_tags: vec![],
+            position: None,
+        }
+    }
+
+    fn formatted(item: OutputItem) -> Value {
+        match item {
+            OutputItem::Formatted(value) => value,
+            _ => panic!("should be formatted"),
+        }
+    }
+
+    #[test]
+    fn test_default_options_keep_item_raw() {
+        let data_item = json_data_item(br#"{"a":1}"#);
+        let item = format_item(&data_item, &OutputOptions::default()).expect("should work");
+        assert!(matches!(item, OutputItem::Raw(_)));
+    }
+
+    #[test]
+    fn test_inline_json_data() {
+        let data_item = json_data_item(br#"{"a":1}"#);
+        let options = OutputOptions {
+            inline_json_max_size: Some(100),
+            ..Default::default()
+        };
+        let value = formatted(format_item(&data_item, &options).expect("should work"));
+        assert_eq!(value["data"], serde_json::json!({ "a": 1 }));
+        assert_eq!(value["data_inlined"], true);
+    }
+
+    #[test]
+    fn test_inline_json_fallbacks() {
+        let options = OutputOptions {
+            inline_json_max_size: Some(5),
+            ..Default::default()
+        };
+
+        for data in [&br#"{"a":1}"#[..], b"{oops"] {
+            let data_item = json_data_item(data);
+            let value = formatted(format_item(&data_item, &options).expect("should work"));
+            assert_eq!(value["data"], Base64(data.to_vec()).to_string());
+            assert!(value.get("data_inlined").is_none());
+        }
+    }
+
+    #[test]
+    fn test_text_anchor() {
+        let options = OutputOptions {
+            text_anchor: true,
+            ..Default::default()
+        };
+        let mut anchor = b"nonce-1".to_vec();
+        anchor.resize(32, 0);
+
+        let mut data_item = json_data_item(b"{}");
+        data_item.anchor = Some(Base64(anchor));
+        assert_eq!(data_item.anchor_string().as_deref(), Some("nonce-1"));
+        let value = formatted(format_item(&data_item, &options).expect("should work"));
+        assert_eq!(value["anchor"], "nonce-1");
+        assert_eq!(value["anchor_text"], true);
+
+        // binary anchor stays base64
+        data_item.anchor = Some(Base64(vec![0xff; 32]));
+        assert_eq!(data_item.anchor_string(), None);
+        let value = formatted(format_item(&data_item, &options).expect("should work"));
+        assert_eq!(value["anchor"], Base64(vec![0xff; 32]).to_string());
+        assert!(value.get("anchor_text").is_none());
+    }
+
+    #[test]
+    fn test_tags_record() {
+        let mut data_item = json_data_item(b"{}");
+        data_item.bundle_id = Base64(vec![1, 2, 3]);
+        data_item.tags.push(BundleTag {
+            name: "Content-Type".to_string(),
+            value: "text/plain".to_string(),
+        });
+        data_item.tags.push(BundleTag {
+            name: "App-Name".to_string(),
+            value: "test".to_string(),
+        });
+
+        assert_eq!(
+            serde_json::to_value(TagsRecord::from(&data_item)).expect("should serialize"),
+            serde_json::json!({
+                "id": "AQID",
+                "tags": {
+                    "App-Name": "test",
+                    "Content-Type": "application/json; charset=utf-8",
+                },
+            })
+        );
+    }
+
+    #[test]
+    fn test_tag_triples() {
+        let mut data_item = json_data_item(b"{}");
+        data_item.bundle_id = Base64(vec![1, 2, 3]);
+        data_item.tags.push(BundleTag {
+            name: "Content-Type".to_string(),
+            value: "text/plain".to_string(),
+        });
+
+        let triples: Vec<_> = TagTriple::all(&data_item)
+            .map(|triple| serde_json::to_value(triple).expect("should serialize"))
+            .collect();
+        assert_eq!(triples.len(), 2);
+        assert_eq!(
+            triples[1],
+            serde_json::json!({
+                "id": "AQID",
+                "name": "Content-Type",
+                "value": "text/plain",
+            })
+        );
+    }
+
+    #[test]
+    fn test_tags_prefix() {
+        let mut data_item = json_data_item(b"{}");
+        data_item.tags.push(BundleTag {
+            name: "Custom:Key".to_string(),
+            value: "value".to_string(),
+        });
+        let custom_tag = serde_json::json!([{ "name": "Key", "value": "value" }]);
+
+        let options = OutputOptions {
+            only_tags_prefix: Some("Custom:".to_string()),
+            flatten_tags_prefix: Some("Custom:".to_string()),
+            ..Default::default()
+        };
+        let value = formatted(format_item(&data_item, &options).expect("should work"));
+        assert_eq!(value["tags"], custom_tag);
+        assert_eq!(
+            value["original_tags"],
+            serde_json::to_value(&data_item.tags).expect("should serialize")
+        );
+
+        let options = OutputOptions {
+            flatten_tags_prefix: Some("Custom:".to_string()),
+            ..Default::default()
+        };
+        let value = formatted(format_item(&data_item, &options).expect("should work"));
+        assert_eq!(value["tags"][0]["name"], "Content-Type");
+        assert_eq!(value["tags"][1], custom_tag[0]);
+
+        // nothing to rewrite
+        let options = OutputOptions {
+            only_tags_prefix: Some("Content-".to_string()),
+            ..Default::default()
+        };
+        data_item.tags.pop();
+        let value = formatted(format_item(&data_item, &options).expect("should work"));
+        assert!(value.get("original_tags").is_none());
+    }
+
+    #[test]
+    fn test_data_preview() {
+        let data_item = json_data_item(b"not json at all");
+        let options = OutputOptions {
+            data_preview: Some(3),
+            ..Default::default()
+        };
+        let item = formatted(format_item(&data_item, &options).expect("should format"));
+        assert_eq!(item["data"], Base64(b"not".to_vec()).to_string());
+        assert_eq!(item["data_truncated"], true);
+        assert_eq!(item["data_len"], 15);
+
+        let options = OutputOptions {
+            data_preview: Some(15),
+            ..Default::default()
+        };
+        let item = formatted(format_item(&data_item, &options).expect("should format"));
+        assert_eq!(item["data"], data_item.data.to_string());
+        assert_eq!(item.get("data_truncated"), None);
+        assert_eq!(item["data_len"], 15);
+
+        let data_item = json_data_item(br#"{"a":1}"#);
+        let options = OutputOptions {
+            data_preview: Some(3),
+            inline_json_max_size: Some(1024),
+            ..Default::default()
+        };
+        let item = formatted(format_item(&data_item, &options).expect("should format"));
+        assert_eq!(item["data"], serde_json::json!({"a": 1}));
+        assert_eq!(item.get("data_len"), None);
+    }
+
+    #[test]
+    fn test_rename_fields() {
+        let data_item = json_data_item(b"{}");
+        let options = OutputOptions {
+            rename_fields: vec![
+                ("bundle_id".to_string(), "id".to_string()),
+                ("owner_public_key".to_string(), "owner".to_string()),
+                ("data_file".to_string(), "file".to_string()),
+            ],
+            ..Default::default()
+        };
+        let value = formatted(format_item(&data_item, &options).expect("should work"));
+        assert_eq!(value["id"], data_item.bundle_id.to_string());
+        assert_eq!(value["owner"], data_item.owner_public_key.to_string());
+        assert!(value.get("bundle_id").is_none());
+        // absent optional field is not renamed into existence
+        assert!(value.get("file").is_none());
+
+        let options = OutputOptions {
+            rename_fields: vec![("bundle_id".to_string(), "signature".to_string())],
+            ..Default::default()
+        };
+        format_item(&data_item, &options).expect_err("should fail");
+    }
+
+    #[test]
+    fn test_with_offsets() {
+        let options = OutputOptions {
+            with_offsets: true,
+            text_anchor: true,
+            ..Default::default()
+        };
+        let mut data_item = json_data_item(b"{}");
+        let item = format_item(&data_item, &options).expect("should work");
+        assert!(matches!(item, OutputItem::Formatted(_)));
+
+        data_item.position = Some(ItemPosition {
+            index: 2,
+            offset: 1000,
+            length: 42,
+        });
+        let item = format_item(&data_item, &options).expect("should work");
+        let value = serde_json::to_value(&item).expect("should serialize");
+        assert_eq!(value["index"], 2);
+        assert_eq!(value["offset"], 1000);
+        assert_eq!(value["length"], 42);
+        assert_eq!(value["signature_name"], "ed25519");
+
+        // raw DataItem gets the fields too
+        let options = OutputOptions {
+            with_offsets: true,
+            ..Default::default()
+        };
+        let item = format_item(&data_item, &options).expect("should work");
+        let value = serde_json::to_value(&item).expect("should serialize");
+        assert_eq!(value["offset"], 1000);
+        let written: DataItem = serde_json::from_value(value).expect("should parse");
+        assert_eq!(written.data, data_item.data);
+    }
+}
@@ -0,0 +1,246 @@
+use arweave_rs::crypto::{base64::Base64, hash::sha256};
+use rsa::{BigUint, PaddingScheme, PublicKey, RsaPublicKey};
+use sha2::{Digest, Sha256};
+use tokio::io::AsyncRead;
+
+use crate::bundle::read_buffer_as_base64;
+
+/// DataItem signature schemes known by the parser, see ANS-104 signature types.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignatureScheme {
+    Arweave,
+    Ed25519,
+    Ethereum,
+    Solana,
+}
+
+/// Signature related DataItem header fields.
+#[derive(Debug)]
+pub struct SignatureHeader {
+    pub signature: Base64,
+    pub owner_public_key: Base64,
+}
+
+impl SignatureScheme {
+    pub const ALL: [Self; 4] = [Self::Arweave, Self::Ed25519, Self::Ethereum, Self::Solana];
+
+    pub fn from_type(signature_type: u16) -> anyhow::Result<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|scheme| scheme.signature_type() == signature_type)
+            .ok_or_else(|| anyhow::anyhow!("Unsupported signature type: {signature_type}"))
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|scheme| scheme.name() == name)
+    }
+
+    pub fn signature_type(self) -> u16 {
+        match self {
+            Self::Arweave => 1,
+            Self::Ed25519 => 2,
+            Self::Ethereum => 3,
+            Self::Solana => 4,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Arweave => "arweave",
+            Self::Ed25519 => "ed25519",
+            Self::Ethereum => "ethereum",
+            Self::Solana => "solana",
+        }
+    }
+
+    pub fn signature_length(self) -> usize {
+        match self {
+            Self::Arweave => 512,
+            Self::Ed25519 | Self::Solana => 64,
+            Self::Ethereum => 65,
+        }
+    }
+
+    pub fn public_key_length(self) -> usize {
+        match self {
+            Self::Arweave => 512,
+            Self::Ed25519 | Self::Solana => 32,
+            Self::Ethereum => 65,
+        }
+    }
+
+    /// Derives DataItem id from its signature. All current schemes use SHA-256 of raw signature bytes.
+    pub fn derive_id(self, signature: &Base64) -> Base64 {
+        let hash = match self {
+            Self::Arweave | Self::Ed25519 | Self::Ethereum | Self::Solana => sha256(&signature.0),
+        };
+        Base64::from(&hash[..])
+    }
+
+    /// Reads signature header fields following the signature type.
+    /// Schemes with extra header fields read them here, keeping DataItem parsing untouched.
+    pub async fn read_header<R>(self, mut reader: R) -> anyhow::Result<SignatureHeader>
+    where
+        R: AsyncRead + Unpin,
+    {
+        let signature =
+            read_buffer_as_base64(&mut reader, self.signature_length(), "signature").await?;
+
+        let owner_public_key =
+            read_buffer_as_base64(&mut reader, self.public_key_length(), "owner public key")
+                .await?;
+
+        let header = SignatureHeader {
+            signature,
+            owner_public_key,
+        };
+        self.validate_header(&header)?;
+        Ok(header)
+    }
+
+    /// Structural sanity check of header fields, catches layout desync early. Does not verify the signature.
+    pub fn validate_header(self, header: &SignatureHeader) -> anyhow::Result<()> {
+        let name = self.name();
+        let signature_length = header.signature.0.len();
+        if signature_length != self.signature_length() {
+            return Err(anyhow::anyhow!(
+                "{name} signature length {signature_length} does not match expected {}",
+                self.signature_length()
+            ));
+        }
+
+        let owner = &header.owner_public_key.0;
+        if owner.len() != self.public_key_length() {
+            return Err(anyhow::anyhow!(
+                "{name} owner public key length {} does not match expected {}",
+                owner.len(),
+                self.public_key_length()
+            ));
+        }
+
+        match self {
+            // 4096 bit RSA modulus always has its highest bit set
+            Self::Arweave if owner[0] & 0x80 == 0 => Err(anyhow::anyhow!(
+                "arweave owner is not a 512 byte RSA modulus (leading byte: {:#04x})",
+                owner[0]
+            )),
+            Self::Ethereum if owner[0] != 0x04 => Err(anyhow::anyhow!(
+                "ethereum owner is not an uncompressed public key (prefix: {:#04x})",
+                owner[0]
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+// public exponent of all arweave keys
+const RSA_EXPONENT: u32 = 65537;
+
+/// Verifies RSA-PSS signature of message with SHA-256 digest, as arweave signatures are,
+/// against public key given by its big endian modulus. Malformed key is reported as invalid signature.
+pub fn verify_rsa_pss(modulus: &[u8], message: &[u8], signature: &[u8]) -> bool {
+    let Ok(public_key) =
+        RsaPublicKey::new(BigUint::from_bytes_be(modulus), BigUint::from(RSA_EXPONENT))
+    else {
+        return false;
+    };
+    // salt is recovered from the signature, rng is used by signing only
+    let padding = PaddingScheme::new_pss::<Sha256, _>(rand::thread_rng());
+    public_key
+        .verify(padding, &Sha256::digest(message), signature)
+        .is_ok()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_known_signature_types() {
+        for scheme in SignatureScheme::ALL {
+            assert_eq!(
+                SignatureScheme::from_type(scheme.signature_type()).expect("should be known"),
+                scheme
+            );
+        }
+        assert!(SignatureScheme::from_type(0).is_err());
+        assert!(SignatureScheme::from_type(5).is_err());
+    }
+
+    #[test]
+    fn test_derive_id() {
+        let signature = Base64(vec![7; 64]);
+        let expected = Base64::from(&sha256(&signature.0)[..]);
+        for scheme in SignatureScheme::ALL {
+            assert_eq!(
+                SignatureScheme::from_name(scheme.name()),
+                Some(scheme),
+                "{}",
+                scheme.name()
+            );
+            assert_eq!(scheme.derive_id(&signature), expected, "{}", scheme.name());
+        }
+        assert_eq!(SignatureScheme::from_name("unknown"), None);
+    }
+
+    #[tokio::test]
+    async fn test_read_ed25519_header() {
+        let mut data = vec![1u8; 64];
+        data.extend([2u8; 32]);
+        data.extend(b"rest");
+
+        let mut reader = data.as_slice();
+        let header = SignatureScheme::Ed25519
+            .read_header(&mut reader)
+            .await
+            .expect("should work");
+
+        assert_eq!(header.signature.0, vec![1u8; 64]);
+        assert_eq!(header.owner_public_key.0, vec![2u8; 32]);
+        assert_eq!(reader, b"rest");
+    }
+
+    #[test]
+    fn test_validate_header_lengths() {
+        let header = SignatureHeader {
+            signature: Base64(vec![1; 63]),
+            owner_public_key: Base64(vec![2; 32]),
+        };
+        assert!(SignatureScheme::Ed25519.validate_header(&header).is_err());
+
+        let header = SignatureHeader {
+            signature: Base64(vec![1; 64]),
+            owner_public_key: Base64(vec![2; 32]),
+        };
+        assert!(SignatureScheme::Ed25519.validate_header(&header).is_ok());
+        assert!(SignatureScheme::Ethereum.validate_header(&header).is_err());
+    }
+
+    #[test]
+    fn test_validate_owner_layout() {
+        let mut header = SignatureHeader {
+            signature: Base64(vec![1; 512]),
+            owner_public_key: Base64(vec![0xc0; 512]),
+        };
+        assert!(SignatureScheme::Arweave.validate_header(&header).is_ok());
+        header.owner_public_key.0[0] = 0;
+        assert!(SignatureScheme::Arweave.validate_header(&header).is_err());
+
+        let mut header = SignatureHeader {
+            signature: Base64(vec![1; 65]),
+            owner_public_key: Base64(vec![0x04; 65]),
+        };
+        assert!(SignatureScheme::Ethereum.validate_header(&header).is_ok());
+        header.owner_public_key.0[0] = 0x02;
+        assert!(SignatureScheme::Ethereum.validate_header(&header).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_read_truncated_header() {
+        let data = vec![1u8; 64];
+        assert!(SignatureScheme::Ethereum
+            .read_header(data.as_slice())
+            .await
+            .is_err());
+    }
+}
@@ -0,0 +1,398 @@
+use async_trait::async_trait;
+use serde::Serialize;
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+
+use crate::{
+    async_json::{ArrayWriter, JsonFormat},
+    async_tar::TarWriter,
+    bundle::DataItem,
+    output::{self, OutputOptions, FORMAT_VERSION, FORMAT_VERSION_KEY},
+};
+
+/// Name of the last tar archive entry, describing all others.
+pub const TAR_MANIFEST_PATH: &str = "manifest.json";
+
+/// Destination of dumped DataItems, every output format is one implementation.
+#[async_trait(?Send)]
+pub trait OutputSink {
+    /// Writes what precedes the first DataItem, e.g. opening bracket or header.
+    async fn open(&mut self) -> anyhow::Result<()>;
+
+    async fn write_item(&mut self, data_item: &DataItem) -> anyhow::Result<()>;
+
+    /// Flushes buffered output so that it can be synced to disk.
+    async fn flush(&mut self) -> anyhow::Result<()>;
+
+    /// Writes what follows the last DataItem and flushes.
+    async fn finish(&mut self) -> anyhow::Result<()>;
+}
+
+/// JSON array of DataItems, optionally wrapped into an object, see [`JsonArraySink::with_header`].
+pub struct JsonArraySink<W> {
+    writer: ArrayWriter<W>,
+    options: OutputOptions,
+    header: Option<serde_json::Map<String, serde_json::Value>>,
+    version_comment: bool,
+}
+
+/// Key of DataItems array in the wrapping object.
+pub const ITEMS_KEY: &str = "items";
+
+impl<W> JsonArraySink<W> {
+    pub fn new(writer: W, format: JsonFormat, options: OutputOptions) -> Self {
+        Self {
+            writer: ArrayWriter::with_format(writer, format),
+            options,
+            header: None,
+            version_comment: false,
+        }
+    }
+
+    pub fn get_mut(&mut self) -> &mut W {
+        self.writer.get_mut()
+    }
+
+    /// Writes object with [`FORMAT_VERSION_KEY`] and header fields followed by DataItems array
+    /// under [`ITEMS_KEY`] instead of bare array.
+    pub fn with_header(mut self, header: serde_json::Map<String, serde_json::Value>) -> Self {
+        self.header = Some(header);
+        self
+    }
+
+    /// Precedes bare array with `// _format_version: N` line. Not valid JSON anymore,
+    /// consumers have to skip the line (or parse JSON with comments).
+    pub fn with_version_comment(mut self) -> Self {
+        self.version_comment = true;
+        self
+    }
+}
+
+#[async_trait(?Send)]
+impl<W> OutputSink for JsonArraySink<W>
+where
+    W: AsyncWrite + Unpin,
+{
+    async fn open(&mut self) -> anyhow::Result<()> {
+        if let Some(header) = &self.header {
+            let writer = self.writer.get_mut();
+            writer.write_all(b"{\n").await?;
+            let version = format!("\"{FORMAT_VERSION_KEY}\": {FORMAT_VERSION},\n");
+            writer.write_all(version.as_bytes()).await?;
+            for (key, value) in header {
+                let field = format!("{}: {},\n", serde_json::to_string(key)?, value);
+                writer.write_all(field.as_bytes()).await?;
+            }
+            writer
+                .write_all(format!("\"{ITEMS_KEY}\": ").as_bytes())
+                .await?;
+        } else if self.version_comment {
+            let comment = format!("// {FORMAT_VERSION_KEY}: {FORMAT_VERSION}\n");
+            self.writer.get_mut().write_all(comment.as_bytes()).await?;
+        }
+        self.writer.write_open_bracket().await
+    }
+
+    async fn write_item(&mut self, data_item: &DataItem) -> anyhow::Result<()> {
+        let output_item = output::format_item(data_item, &self.options)?;
+        self.writer.write_item(&output_item).await
+    }
+
+    async fn flush(&mut self) -> anyhow::Result<()> {
+        self.writer.get_mut().flush().await?;
+        Ok(())
+    }
+
+    async fn finish(&mut self) -> anyhow::Result<()> {
+        self.writer.write_close_bracket().await?;
+        if self.header.is_some() {
+            self.writer.get_mut().write_all(b"}\n").await?;
+        }
+        self.flush().await
+    }
+}
+
+/// Every DataItem as pretty JSON in its own `<prefix>.<index>.json` file.
+pub struct SplitSink {
+    prefix: String,
+    options: OutputOptions,
+    index: usize,
+}
+
+impl SplitSink {
+    pub fn new(prefix: String, options: OutputOptions) -> Self {
+        Self {
+            prefix,
+            options,
+            index: 0,
+        }
+    }
+}
+
+#[async_trait(?Send)]
+impl OutputSink for SplitSink {
+    async fn open(&mut self) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    async fn write_item(&mut self, data_item: &DataItem) -> anyhow::Result<()> {
+        let output_item = output::format_item(data_item, &self.options)?;
+        let filename = format!("{}.{:06}.json", self.prefix, self.index);
+        tokio::fs::write(&filename, serde_json::to_vec_pretty(&output_item)?).await?;
+        self.index += 1;
+        Ok(())
+    }
+
+    // every file is written in one go
+    async fn flush(&mut self) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    async fn finish(&mut self) -> anyhow::Result<()> {
+        Ok(())
+    }
+}
+
+/// Data of every DataItem as tar archive entry named `<DataItem_ID>[.<extension of Content-Type>]`,
+/// followed by [`TAR_MANIFEST_PATH`] entry listing DataItems with their tags.
+/// Spilled data files are removed once they are copied into the archive.
+pub struct TarSink<W> {
+    writer: TarWriter<W>,
+    manifest: Vec<TarManifestEntry>,
+}
+
+#[derive(Debug, Serialize)]
+struct TarManifestEntry {
+    id: String,
+    path: String,
+    size: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    data_sha256: Option<String>,
+    tags: serde_json::Value,
+}
+
+impl<W> TarSink<W> {
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer: TarWriter::new(writer),
+            manifest: vec![],
+        }
+    }
+}
+
+#[async_trait(?Send)]
+impl<W> OutputSink for TarSink<W>
+where
+    W: AsyncWrite + Unpin,
+{
+    async fn open(&mut self) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    async fn write_item(&mut self, data_item: &DataItem) -> anyhow::Result<()> {
+        let path = match data_item
+            .get_tag("Content-Type")
+            .and_then(content_type_extension)
+        {
+            Some(extension) => format!("{}.{extension}", data_item.bundle_id),
+            None => data_item.bundle_id.to_string(),
+        };
+        match &data_item.data_file {
+            Some(data_file) => {
+                let file = tokio::fs::File::open(&data_file.path).await?;
+                self.writer
+                    .append_reader(
+                        &path,
+                        data_file.size as u64,
+                        tokio::io::BufReader::new(file),
+                    )
+                    .await?;
+                // archive is the only copy of spilled data which is kept
+                tokio::fs::remove_file(&data_file.path).await?;
+            }
+            None => self.writer.append(&path, &data_item.data.0).await?,
+        }
+        self.manifest.push(TarManifestEntry {
+            id: data_item.bundle_id.to_string(),
+            path,
+            size: data_item.data_len(),
+            data_sha256: data_item.data_sha256.clone(),
+            tags: serde_json::to_value(&data_item.tags)?,
+        });
+        Ok(())
+    }
+
+    async fn flush(&mut self) -> anyhow::Result<()> {
+        self.writer.get_mut().flush().await?;
+        Ok(())
+    }
+
+    async fn finish(&mut self) -> anyhow::Result<()> {
+        let manifest = serde_json::to_vec_pretty(&self.manifest)?;
+        self.writer.append(TAR_MANIFEST_PATH, &manifest).await?;
+        self.writer.finish().await
+    }
+}
+
+// extension of well known content types, MIME parameters are ignored
+fn content_type_extension(content_type: &str) -> Option<&'static str> {
+    let mime = content_type.split(';').next()?.trim();
+    let extension = match mime.to_ascii_lowercase().as_str() {
+        "application/json" => "json",
+        "application/pdf" => "pdf",
+        "application/javascript" | "text/javascript" => "js",
+        "text/plain" => "txt",
+        "text/html" => "html",
+        "text/css" => "css",
+        "text/markdown" => "md",
+        "image/png" => "png",
+        "image/jpeg" => "jpg",
+        "image/gif" => "gif",
+        "image/webp" => "webp",
+        "image/svg+xml" => "svg",
+        "audio/mpeg" => "mp3",
+        "video/mp4" => "mp4",
+        _ => return None,
+    };
+    Some(extension)
+}
+
+#[cfg(test)]
+mod test {
+    use futures_util::TryStreamExt;
+
+    use super::*;
+    use crate::bundle;
+
+    async fn fixture_data_items() -> Vec<DataItem> {
+        let hex_str = include_str!("../res/uYpAeGCj8Xe_J0sKiZ_aJ4Zl1zQLgDH5ia-pqtNLJEA_data.hex");
+        let data = hex::decode(hex_str).expect("should parse");
+        bundle::ans104_bundle_data_item_stream(data.as_slice())
+            .try_collect()
+            .await
+            .expect("should parse")
+    }
+
+    #[tokio::test]
+    async fn test_json_array_sink() {
+        let data_items = fixture_data_items().await;
+
+        let mut output = vec![];
+        let mut sink: Box<dyn OutputSink + '_> = Box::new(JsonArraySink::new(
+            &mut output,
+            JsonFormat::Compact,
+            OutputOptions::default(),
+        ));
+        sink.open().await.expect("should not fail");
+        for data_item in &data_items {
+            sink.write_item(data_item).await.expect("should not fail");
+        }
+        sink.finish().await.expect("should not fail");
+        drop(sink);
+
+        let written: Vec<DataItem> = serde_json::from_slice(&output).expect("should parse");
+        assert_eq!(written.len(), data_items.len());
+        assert_eq!(written[3].bundle_id, data_items[3].bundle_id);
+    }
+
+    #[tokio::test]
+    async fn test_json_array_sink_with_header() {
+        let data_items = fixture_data_items().await;
+        let header = serde_json::json!({ "bundle_tags": { "Bundle-Format": "binary" } });
+
+        let mut output = vec![];
+        let mut sink =
+            JsonArraySink::new(&mut output, JsonFormat::Pretty, OutputOptions::default())
+                .with_header(header.as_object().expect("should be object").clone());
+        sink.open().await.expect("should not fail");
+        for data_item in &data_items {
+            sink.write_item(data_item).await.expect("should not fail");
+        }
+        sink.finish().await.expect("should not fail");
+
+        let written: serde_json::Value = serde_json::from_slice(&output).expect("should parse");
+        assert_eq!(written[FORMAT_VERSION_KEY], FORMAT_VERSION);
+        assert_eq!(written["bundle_tags"], header["bundle_tags"]);
+        let items = written[ITEMS_KEY].as_array().expect("should be array");
+        assert_eq!(items.len(), data_items.len());
+        assert_eq!(items[3]["bundle_id"], data_items[3].bundle_id.to_string());
+    }
+
+    #[tokio::test]
+    async fn test_json_array_sink_with_version_comment() {
+        let data_items = fixture_data_items().await;
+
+        let mut output = vec![];
+        let mut sink =
+            JsonArraySink::new(&mut output, JsonFormat::Compact, OutputOptions::default())
+                .with_version_comment();
+        sink.open().await.expect("should not fail");
+        sink.write_item(&data_items[0])
+            .await
+            .expect("should not fail");
+        sink.finish().await.expect("should not fail");
+
+        let output = String::from_utf8(output).expect("should be text");
+        let (comment, array) = output.split_once('\n').expect("should have lines");
+        assert_eq!(comment, format!("// _format_version: {FORMAT_VERSION}"));
+        let written: Vec<DataItem> = serde_json::from_str(array).expect("should parse");
+        assert_eq!(written[0].bundle_id, data_items[0].bundle_id);
+    }
+
+    #[tokio::test]
+    async fn test_tar_sink() {
+        let data_items = fixture_data_items().await;
+
+        let mut archive = vec![];
+        let mut sink = TarSink::new(&mut archive);
+        sink.open().await.expect("should not fail");
+        for data_item in &data_items {
+            sink.write_item(data_item).await.expect("should not fail");
+        }
+        sink.finish().await.expect("should not fail");
+
+        let entries = crate::async_tar::test::read_entries(&archive);
+        assert_eq!(entries.len(), data_items.len() + 1);
+        let (path, data) = &entries[0];
+        // fixture DataItems have no Content-Type tag
+        assert_eq!(path, &data_items[0].bundle_id.to_string());
+        assert_eq!(data, &data_items[0].data.0);
+
+        let (path, manifest) = entries.last().expect("should be present");
+        assert_eq!(path, TAR_MANIFEST_PATH);
+        let manifest: serde_json::Value = serde_json::from_slice(manifest).expect("should parse");
+        assert_eq!(manifest[3]["id"], data_items[3].bundle_id.to_string());
+        assert_eq!(manifest[3]["size"], data_items[3].data.0.len());
+    }
+
+    #[test]
+    fn test_content_type_extension() {
+        assert_eq!(content_type_extension("image/png"), Some("png"));
+        assert_eq!(
+            content_type_extension("Text/HTML; charset=utf-8"),
+            Some("html")
+        );
+        assert_eq!(content_type_extension("application/x-unknown"), None);
+    }
+
+    #[tokio::test]
+    async fn test_split_sink() {
+        let data_items = fixture_data_items().await;
+        let dir = std::env::temp_dir().join(format!("arweave-dumper-split-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).expect("should create");
+        let prefix = dir.join("bundle").to_string_lossy().to_string();
+
+        let mut sink = SplitSink::new(prefix.clone(), OutputOptions::default());
+        sink.open().await.expect("should not fail");
+        for data_item in &data_items {
+            sink.write_item(data_item).await.expect("should not fail");
+        }
+        sink.finish().await.expect("should not fail");
+
+        let written: DataItem = serde_json::from_slice(
+            &std::fs::read(format!("{prefix}.000001.json")).expect("should be written"),
+        )
+        .expect("should parse");
+        assert_eq!(written.bundle_id, data_items[1].bundle_id);
+        std::fs::remove_dir_all(&dir).expect("should remove");
+    }
+}
@@ -0,0 +1,154 @@
+use std::{collections::BTreeMap, fmt};
+
+use serde::Serialize;
+
+use crate::bundle::DataItem;
+
+/// Tag whose values DataItems are grouped by unless [`Stats::with_group_tag`] is used.
+pub const DEFAULT_GROUP_TAG: &str = "App-Name";
+
+/// Aggregate statistics of dumped DataItems.
+#[derive(Debug, Serialize)]
+pub struct Stats {
+    pub total_items: usize,
+    pub total_data_bytes: usize,
+    pub by_signature_type: BTreeMap<String, usize>,
+    pub by_content_type: BTreeMap<String, usize>,
+    pub min_data_size: Option<usize>,
+    pub max_data_size: Option<usize>,
+    /// name of the tag counted in `by_group_tag`
+    pub group_tag: String,
+    /// DataItems by value of `group_tag` tag, ones without it are not counted
+    pub by_group_tag: BTreeMap<String, usize>,
+}
+
+impl Default for Stats {
+    fn default() -> Self {
+        Self::with_group_tag(DEFAULT_GROUP_TAG)
+    }
+}
+
+impl Stats {
+    pub fn with_group_tag(group_tag: &str) -> Self {
+        Self {
+            total_items: 0,
+            total_data_bytes: 0,
+            by_signature_type: BTreeMap::new(),
+            by_content_type: BTreeMap::new(),
+            min_data_size: None,
+            max_data_size: None,
+            group_tag: group_tag.to_string(),
+            by_group_tag: BTreeMap::new(),
+        }
+    }
+
+    pub fn update(&mut self, data_item: &DataItem) {
+        let data_size = data_item.data_len();
+
+        self.total_items += 1;
+        self.total_data_bytes += data_size;
+        *self
+            .by_signature_type
+            .entry(data_item.signature_name.clone())
+            .or_default() += 1;
+        if let Some(content_type) = data_item.get_tag("Content-Type") {
+            *self
+                .by_content_type
+                .entry(content_type.to_string())
+                .or_default() += 1;
+        }
+        if let Some(value) = data_item.get_tag(&self.group_tag) {
+            *self.by_group_tag.entry(value.to_string()).or_default() += 1;
+        }
+        self.min_data_size = Some(self.min_data_size.map_or(data_size, |v| v.min(data_size)));
+        self.max_data_size = Some(self.max_data_size.map_or(data_size, |v| v.max(data_size)));
+    }
+}
+
+impl fmt::Display for Stats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Total items: {}", self.total_items)?;
+        writeln!(f, "Total data bytes: {}", self.total_data_bytes)?;
+        writeln!(
+            f,
+            "Data size min: {} max: {}",
+            self.min_data_size.unwrap_or_default(),
+            self.max_data_size.unwrap_or_default()
+        )?;
+        writeln!(f, "By signature type:")?;
+        for (name, count) in &self.by_signature_type {
+            writeln!(f, "  {name}: {count}")?;
+        }
+        writeln!(f, "By content type:")?;
+        for (name, count) in &self.by_content_type {
+            writeln!(f, "  {name}: {count}")?;
+        }
+        writeln!(f, "By {}:", self.group_tag)?;
+        for (value, count) in &self.by_group_tag {
+            writeln!(f, "  {value}: {count}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::bundle::ans104_bundle_data_item_stream;
+    use futures_util::stream::TryStreamExt;
+
+    #[tokio::test]
+    async fn test_sample_bundle_stats() {
+        let hex_str = include_str!("../res/uYpAeGCj8Xe_J0sKiZ_aJ4Zl1zQLgDH5ia-pqtNLJEA_data.hex");
+        let data = hex::decode(hex_str).expect("should parse");
+
+        let data_items = ans104_bundle_data_item_stream(data.as_slice())
+            .try_collect::<Vec<DataItem>>()
+            .await
+            .expect("should work");
+
+        let mut stats = Stats::default();
+        data_items.iter().for_each(|item| stats.update(item));
+
+        let sizes = data_items.iter().map(|item| item.data.0.len());
+        assert_eq!(stats.total_items, 4);
+        assert_eq!(stats.total_data_bytes, sizes.clone().sum::<usize>());
+        assert_eq!(stats.min_data_size, sizes.clone().min());
+        assert_eq!(stats.max_data_size, sizes.max());
+        assert_eq!(stats.by_signature_type.values().sum::<usize>(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_stats_by_group_tag() {
+        let hex_str = include_str!("../res/uYpAeGCj8Xe_J0sKiZ_aJ4Zl1zQLgDH5ia-pqtNLJEA_data.hex");
+        let data = hex::decode(hex_str).expect("should parse");
+        let data_items = ans104_bundle_data_item_stream(data.as_slice())
+            .try_collect::<Vec<DataItem>>()
+            .await
+            .expect("should work");
+        let (name, value) = data_items[0]
+            .tags
+            .first()
+            .map(|tag| (tag.name.clone(), tag.value.clone()))
+            .expect("should have tags");
+
+        let mut stats = Stats::with_group_tag(&name);
+        data_items.iter().for_each(|item| stats.update(item));
+
+        let expected = data_items
+            .iter()
+            .filter(|item| item.get_tag(&name) == Some(value.as_str()))
+            .count();
+        assert_eq!(stats.by_group_tag.get(&value), Some(&expected));
+        assert!(stats
+            .to_string()
+            .contains(&format!("By {name}:\n  {value}: {expected}")));
+    }
+
+    #[test]
+    fn test_empty_stats() {
+        let stats = Stats::default();
+        assert_eq!(stats.total_items, 0);
+        assert_eq!(stats.min_data_size, None);
+    }
+}